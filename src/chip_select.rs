@@ -38,7 +38,7 @@ where
     busy: BusyPin,
   ) -> Result<Self, WifiNinaChipSelectError<CsPin::Error, BusyPin::Error>> {
     cs.set_high()
-      .map_err(|err| WifiNinaChipSelectError::CsPinError(err))?;
+      .map_err(WifiNinaChipSelectError::CsPinError)?;
 
     Ok(WifiNinaChipSelect {
       spi: core::marker::PhantomData,
@@ -58,13 +58,23 @@ where
     self
       .cs
       .set_low()
-      .map_err(|err| WifiNinaChipSelectError::CsPinError(err))?;
+      .map_err(WifiNinaChipSelectError::CsPinError)?;
 
     self.wait_for_busy(timer, 1_000.ms(), true)?;
 
     Ok(SafeSpi::new(spi, self))
   }
 
+  // Waits for the device to finish handling the last command, for commands
+  // that take longer than select allows for or whose responses we don’t read.
+  pub fn wait_for_ready(
+    &mut self,
+    timer: &mut impl CountDown<Time = impl From<Milliseconds>>,
+    timeout: Milliseconds,
+  ) -> Result<(), WifiNinaChipSelectError<CsPin::Error, BusyPin::Error>> {
+    self.wait_for_busy(timer, timeout, false)
+  }
+
   fn wait_for_busy(
     &mut self,
     timer: &mut impl CountDown<Time = impl From<Milliseconds>>,
@@ -97,7 +107,7 @@ where
     self.last_deselect_err = self
       .cs
       .set_high()
-      .map_err(|err| WifiNinaChipSelectError::CsPinError(err))
+      .map_err(WifiNinaChipSelectError::CsPinError)
       .err();
   }
 }
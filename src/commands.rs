@@ -1,5 +1,9 @@
+pub mod firmware;
+pub mod fs;
 pub mod network;
+pub mod pins;
 pub mod socket;
+pub mod tls;
 pub mod wifi;
 
 use embedded_hal::digital::v2::{InputPin, OutputPin};
@@ -31,9 +35,11 @@ pub enum NinaCommand {
     GetIpAddress = 0x21,
     GetMacAddress = 0x22,
     GetCurrentSsid = 0x23,
+    GetCurrentBssid = 0x24,
     GetCurrentRssi = 0x25,
     GetCurrentEnct = 0x26,
     ScanNetworks = 0x27,
+    StartServerTcp = 0x28,
 
     GetSocket = 0x3F,
     GetStateTcp = 0x29,
@@ -53,10 +59,18 @@ pub enum NinaCommand {
     GetHostByName = 0x35,
     StartScanNetworks = 0x36,
     GetFirmwareVersion = 0x37,
+    SendDataUdp = 0x39,
+    GetRemoteData = 0x3A,
+    GetTime = 0x3B,
+    GetIdxBssid = 0x3C,
+    GetIdxChannel = 0x3D,
     Ping = 0x3E,
 
+    SetClientCert = 0x40,
+    SetCertKey = 0x41,
     SendDataTcp = 0x44,
     GetDatabufTcp = 0x45,
+    InsertDatabuf = 0x46,
 
     SetEnterpriseIdent = 0x4A,
     SetEnterpriseUsername = 0x4B,
@@ -66,15 +80,25 @@ pub enum NinaCommand {
     SetPinMode = 0x50,
     SetDigitalWrite = 0x51,
     SetAnalogWrite = 0x52,
+    GetDigitalRead = 0x53,
+    GetAnalogRead = 0x54,
+
+    WriteFile = 0x60,
+    ReadFile = 0x61,
+    DeleteFile = 0x62,
+    ExistsFile = 0x63,
+    ApplyOta = 0x65,
+    RenameFile = 0x66,
+    DownloadOta = 0x67,
 
     Start = 0xE0,
     End = 0xEE,
     Error = 0xEF,
 }
 
-impl Into<u8> for NinaCommand {
-    fn into(self) -> u8 {
-        self as u8
+impl From<NinaCommand> for u8 {
+    fn from(c: NinaCommand) -> u8 {
+        c as u8
     }
 }
 
@@ -86,9 +110,9 @@ enum NinaResponse {
     Error = 255,
 }
 
-impl Into<u8> for NinaResponse {
-    fn into(self) -> u8 {
-        self as u8
+impl From<NinaResponse> for u8 {
+    fn from(r: NinaResponse) -> u8 {
+        r as u8
     }
 }
 
@@ -135,6 +159,9 @@ where
         }
     }
 
+    // is_multiple_of is only stable since Rust 1.87, so the padding below sticks
+    // with %.
+    #[allow(unknown_lints, clippy::manual_is_multiple_of)]
     fn send_command(
         &mut self,
         spi: &mut Spi,
@@ -180,17 +207,17 @@ where
             match p {
                 SendParam::Byte(b) => {
                     write_len(&mut spi, 1)?;
-                    write_bytes(&mut spi, &mut [*b].into_iter().cloned())?;
+                    write_bytes(&mut spi, &mut [*b].iter().cloned())?;
                 }
 
                 SendParam::Word(w) => {
                     write_len(&mut spi, 2)?;
-                    write_bytes(&mut spi, &mut w.to_be_bytes().into_iter().cloned())?;
+                    write_bytes(&mut spi, &mut w.to_be_bytes().iter().cloned())?;
                 }
 
                 SendParam::LEWord(w) => {
                     write_len(&mut spi, 2)?;
-                    write_bytes(&mut spi, &mut w.to_le_bytes().into_iter().cloned())?;
+                    write_bytes(&mut spi, &mut w.to_le_bytes().iter().cloned())?;
                 }
 
                 SendParam::Bytes(it) => {
@@ -230,17 +257,15 @@ where
         let use_16_bit_length = params.use_16_bit_length();
 
         let read_len = |spi: &mut Spi, expect: Option<usize>| -> Result<usize, Error<SpiError>> {
-            let len: usize;
-
-            if use_16_bit_length {
+            let len: usize = if use_16_bit_length {
                 let bits = [
                     spi.transfer_byte().map_err(Error::spi)?,
                     spi.transfer_byte().map_err(Error::spi)?,
                 ];
 
-                len = u16::from_be_bytes(bits) as usize;
+                u16::from_be_bytes(bits) as usize
             } else {
-                len = spi.transfer_byte().map_err(Error::spi)? as usize;
+                spi.transfer_byte().map_err(Error::spi)? as usize
             };
 
             if let Some(expect) = expect {
@@ -249,7 +274,7 @@ where
                 }
             }
 
-            return Ok(len);
+            Ok(len)
         };
 
         let param_count: u8 = spi.transfer_byte().map_err(Error::spi)?;
@@ -258,7 +283,7 @@ where
         for param_handler in params {
             if param_idx == param_count {
                 match param_handler {
-                    RecvParam::OptionalByte(_) => continue,
+                    RecvParam::OptionalByte(_) | RecvParam::OptionalBuffer(..) => continue,
                     _ => return Err(Error::MissingParam(param_idx)),
                 }
             };
@@ -321,6 +346,16 @@ where
                         arr[i] = spi.transfer_byte().map_err(Error::spi)?;
                     }
                 }
+
+                RecvParam::OptionalBuffer(arr, ref mut op) => {
+                    let len = read_len(&mut spi, None)?;
+
+                    for i in 0..len {
+                        arr[i] = spi.transfer_byte().map_err(Error::spi)?;
+                    }
+
+                    op.replace(len);
+                }
             };
 
             param_idx += 1;
@@ -371,6 +406,7 @@ pub enum RecvParam<'a> {
     LEWord(&'a mut u16),
     ByteArray(&'a mut [u8]),
     Buffer(&'a mut [u8], &'a mut usize),
+    OptionalBuffer(&'a mut [u8], &'a mut Option<usize>),
 }
 
 pub struct Params<'a, P> {
@@ -404,6 +440,10 @@ impl<'a, P> Params<'a, P> {
         self.params.len() as u8
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
     pub fn use_16_bit_length(&self) -> bool {
         self.use_16_bit_length
     }
@@ -414,6 +454,6 @@ impl<'a, P> core::iter::IntoIterator for Params<'a, P> {
     type IntoIter = core::slice::IterMut<'a, P>;
 
     fn into_iter(self) -> core::slice::IterMut<'a, P> {
-        self.params.into_iter()
+        self.params.iter_mut()
     }
 }
@@ -0,0 +1,173 @@
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+use nb::block;
+
+use crate::commands::*;
+use crate::util::millis::Milliseconds;
+use crate::{Error, WifiNina};
+
+// How long to give the firmware to download a new image. It handles the whole
+// download before responding.
+const OTA_DOWNLOAD_TIMEOUT: Milliseconds = Milliseconds(120_000);
+
+// How long to give the firmware to switch its boot partition over to the
+// downloaded image.
+const OTA_APPLY_TIMEOUT: Milliseconds = Milliseconds(10_000);
+
+// The first boot of a new image takes longer than reset allows for.
+const OTA_FIRST_BOOT: Milliseconds = Milliseconds(3_000);
+
+// Field order matters here: the derived ordering compares major, then minor,
+// then patch.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl FirmwareVersion {
+    pub const fn new(major: u8, minor: u8, patch: u8) -> Self {
+        FirmwareVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    // Parses the "major.minor.patch" string that the firmware reports, which
+    // may be NUL-terminated.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        let mut parts = core::str::from_utf8(&bytes[..end]).ok()?.split('.');
+
+        let version = FirmwareVersion {
+            major: parts.next()?.parse().ok()?,
+            minor: parts.next()?.parse().ok()?,
+            patch: parts.next()?.parse().ok()?,
+        };
+
+        match parts.next() {
+            None => Some(version),
+            Some(_) => None,
+        }
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(fmt, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
+    WifiNina<CsPin, BusyPin, Spi, CountDown>
+where
+    BusyPin: InputPin,
+    CsPin: OutputPin,
+    Spi: FullDuplex<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::Write<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SpiError>,
+    CountDown: embedded_hal::timer::CountDown<Time = CountDownTime>,
+    CountDownTime: From<Milliseconds>,
+{
+    pub fn firmware_version(&mut self, spi: &mut Spi) -> Result<FirmwareVersion, Error<SpiError>> {
+        let mut version = [0u8; 16];
+        let mut len: usize = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetFirmwareVersion,
+            Params::none(),
+            Params::of(&mut [RecvParam::Buffer(&mut version, &mut len)]),
+        )?;
+
+        FirmwareVersion::parse(&version[..len]).ok_or(Error::InvalidFirmwareVersion)
+    }
+
+    // Has the firmware download a new image of itself from url, to be
+    // installed by ota_apply.
+    //
+    // This blocks for the whole download, and fails with
+    // Error::ChipSelectTimeout if it takes longer than two minutes.
+    pub fn ota_download(&mut self, spi: &mut Spi, url: &str) -> Result<(), Error<SpiError>> {
+        let mut result: u8 = 0;
+
+        self.send_command(
+            spi,
+            NinaCommand::DownloadOta,
+            Params::of(&mut [SendParam::Bytes(&mut url.bytes())]),
+        )?;
+
+        // The usual wait for a response is far shorter than a download.
+        self.chip_select
+            .wait_for_ready(&mut self.timer, OTA_DOWNLOAD_TIMEOUT)?;
+
+        self.receive_response(
+            spi,
+            NinaCommand::DownloadOta,
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        match result {
+            0 => Err(Error::OtaDownloadFailed),
+            _ => Ok(()),
+        }
+    }
+
+    // Installs the image from ota_download, resets the chip so that it boots
+    // into it, and returns the version that it’s now running.
+    pub fn ota_apply<ResetPin>(
+        &mut self,
+        spi: &mut Spi,
+        reset: &mut ResetPin,
+    ) -> Result<FirmwareVersion, Error<SpiError>>
+    where
+        ResetPin: OutputPin,
+    {
+        // The firmware restarts itself once it’s swapped images, so there’s no
+        // response to read. We still wait for it to be ready again so that the
+        // reset doesn’t interrupt the swap.
+        self.send_command(spi, NinaCommand::ApplyOta, Params::none())?;
+        self.chip_select
+            .wait_for_ready(&mut self.timer, OTA_APPLY_TIMEOUT)?;
+
+        self.reset(reset)?;
+
+        self.timer.start(OTA_FIRST_BOOT);
+        block!(self.timer.wait()).ok();
+
+        self.firmware_version(spi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FirmwareVersion;
+
+    #[test]
+    fn parses_nul_terminated_version() {
+        assert_eq!(
+            FirmwareVersion::parse(b"1.4.8\0"),
+            Some(FirmwareVersion::new(1, 4, 8))
+        );
+        assert_eq!(
+            FirmwareVersion::parse(b"10.0.12"),
+            Some(FirmwareVersion::new(10, 0, 12))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_version() {
+        assert_eq!(FirmwareVersion::parse(b"1.4"), None);
+        assert_eq!(FirmwareVersion::parse(b"1.4.8.2"), None);
+        assert_eq!(FirmwareVersion::parse(b"1.x.8"), None);
+    }
+
+    #[test]
+    fn orders_by_major_minor_patch() {
+        assert!(FirmwareVersion::new(1, 4, 8) < FirmwareVersion::new(1, 5, 0));
+        assert!(FirmwareVersion::new(1, 10, 0) > FirmwareVersion::new(1, 9, 9));
+        assert!(FirmwareVersion::new(2, 0, 0) > FirmwareVersion::new(1, 9, 9));
+    }
+}
@@ -0,0 +1,246 @@
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+use embedded_hal::timer::CountDown;
+
+use crate::commands::*;
+use crate::util::millis::Milliseconds;
+use crate::{Error, WifiNina};
+
+// File data goes over in a single parameter, which has an 8-bit length, so
+// larger reads and writes are split into chunks of this size.
+const MAX_FILE_CHUNK: usize = 255;
+
+// The firmware copies paths into a fixed-size buffer.
+const MAX_PATH_LEN: usize = 32;
+
+fn check_path<SpiError>(path: &str) -> Result<(), Error<SpiError>> {
+    if path.len() > MAX_PATH_LEN {
+        Err(Error::InvalidPath)
+    } else {
+        Ok(())
+    }
+}
+
+// Files live on the firmware’s SPIFFS partition. Paths need to include its
+// "/fs/" mount point.
+impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
+    WifiNina<CsPin, BusyPin, Spi, CountDown>
+where
+    BusyPin: InputPin,
+    CsPin: OutputPin,
+    Spi: FullDuplex<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::Write<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SpiError>,
+    CountDown: embedded_hal::timer::CountDown<Time = CountDownTime>,
+    CountDownTime: From<Milliseconds>,
+{
+    pub fn file_write(
+        &mut self,
+        spi: &mut Spi,
+        path: &str,
+        offset: u32,
+        data: &[u8],
+    ) -> Result<(), Error<SpiError>> {
+        check_path(path)?;
+
+        let mut offset = offset;
+
+        for chunk in data.chunks(MAX_FILE_CHUNK) {
+            let mut result: u8 = 0;
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::WriteFile,
+                Params::of(&mut [
+                    SendParam::Bytes(&mut offset.to_le_bytes().iter().cloned()),
+                    SendParam::Bytes(&mut (chunk.len() as u32).to_le_bytes().iter().cloned()),
+                    SendParam::Bytes(&mut path.bytes()),
+                    SendParam::Bytes(&mut chunk.iter().cloned()),
+                ]),
+                Params::of(&mut [RecvParam::Byte(&mut result)]),
+            )?;
+
+            // This is how much was written, which falls short when the
+            // filesystem fills up.
+            if result as usize != chunk.len() {
+                return Err(Error::FileOperationFailed);
+            }
+
+            offset += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    // Reads from the file into buf, returning how many bytes were read. This is
+    // less than buf’s length if the end of the file was reached.
+    pub fn file_read(
+        &mut self,
+        spi: &mut Spi,
+        path: &str,
+        offset: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, Error<SpiError>> {
+        check_path(path)?;
+
+        let mut total: usize = 0;
+
+        for chunk in buf.chunks_mut(MAX_FILE_CHUNK) {
+            let chunk_len = chunk.len();
+            let mut read: usize = 0;
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::ReadFile,
+                Params::of(&mut [
+                    SendParam::Bytes(&mut (offset + total as u32).to_le_bytes().iter().cloned()),
+                    SendParam::Bytes(&mut (chunk_len as u32).to_le_bytes().iter().cloned()),
+                    SendParam::Bytes(&mut path.bytes()),
+                ]),
+                Params::of(&mut [RecvParam::Buffer(chunk, &mut read)]),
+            )?;
+
+            total += read;
+
+            if read < chunk_len {
+                break;
+            }
+        }
+
+        Ok(total)
+    }
+
+    pub fn file_delete(&mut self, spi: &mut Spi, path: &str) -> Result<(), Error<SpiError>> {
+        check_path(path)?;
+
+        let mut result: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::DeleteFile,
+            Params::of(&mut [
+                SendParam::Bytes(&mut 0u32.to_le_bytes().iter().cloned()),
+                SendParam::Bytes(&mut 0u32.to_le_bytes().iter().cloned()),
+                SendParam::Bytes(&mut path.bytes()),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        // Deleting a file that doesn’t exist also fails.
+        match result {
+            0 => Ok(()),
+            _ => Err(Error::FileOperationFailed),
+        }
+    }
+
+    // Returns the file’s size, or None if it doesn’t exist.
+    pub fn file_exists(
+        &mut self,
+        spi: &mut Spi,
+        path: &str,
+    ) -> Result<Option<u32>, Error<SpiError>> {
+        check_path(path)?;
+
+        let mut size = [0u8; 4];
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::ExistsFile,
+            Params::of(&mut [
+                SendParam::Bytes(&mut 0u32.to_le_bytes().iter().cloned()),
+                SendParam::Bytes(&mut 0u32.to_le_bytes().iter().cloned()),
+                SendParam::Bytes(&mut path.bytes()),
+            ]),
+            Params::of(&mut [RecvParam::ByteArray(&mut size)]),
+        )?;
+
+        // Missing files are reported as a size of -1.
+        match i32::from_le_bytes(size) {
+            size if size < 0 => Ok(None),
+            size => Ok(Some(size as u32)),
+        }
+    }
+
+    pub fn file_rename(
+        &mut self,
+        spi: &mut Spi,
+        from: &str,
+        to: &str,
+    ) -> Result<(), Error<SpiError>> {
+        check_path(from)?;
+        check_path(to)?;
+
+        let mut errno: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::RenameFile,
+            Params::of(&mut [
+                SendParam::Bytes(&mut from.bytes()),
+                SendParam::Bytes(&mut to.bytes()),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut errno)]),
+        )?;
+
+        match errno {
+            0 => Ok(()),
+            _ => Err(Error::FileOperationFailed),
+        }
+    }
+
+    pub fn fs<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+    ) -> NinaFs<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime> {
+        NinaFs::new(spi, self)
+    }
+}
+
+pub struct NinaFs<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    spi: &'a mut S,
+    wifi: &'a mut WifiNina<CS, B, S, T>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> NinaFs<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn new(spi: &'a mut S, wifi: &'a mut WifiNina<CS, B, S, T>) -> Self {
+        NinaFs { spi, wifi }
+    }
+
+    pub fn write(&mut self, path: &str, offset: u32, data: &[u8]) -> Result<(), Error<SE>> {
+        self.wifi.file_write(self.spi, path, offset, data)
+    }
+
+    pub fn read(&mut self, path: &str, offset: u32, buf: &mut [u8]) -> Result<usize, Error<SE>> {
+        self.wifi.file_read(self.spi, path, offset, buf)
+    }
+
+    pub fn delete(&mut self, path: &str) -> Result<(), Error<SE>> {
+        self.wifi.file_delete(self.spi, path)
+    }
+
+    pub fn exists(&mut self, path: &str) -> Result<Option<u32>, Error<SE>> {
+        self.wifi.file_exists(self.spi, path)
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), Error<SE>> {
+        self.wifi.file_rename(self.spi, from, to)
+    }
+}
@@ -1,6 +1,8 @@
 use embedded_hal::digital::v2::{InputPin, OutputPin};
 use embedded_hal::spi::FullDuplex;
+use embedded_hal::timer::CountDown;
 
+use crate::commands::socket::Destination;
 use crate::commands::*;
 use crate::util::millis::Milliseconds;
 use crate::{Error, WifiNina};
@@ -12,6 +14,73 @@ pub struct NetworkInfo {
   pub gateway_ip: [u8; 4],
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpConfig {
+  Dhcp,
+  Static {
+    ip: [u8; 4],
+    gateway: [u8; 4],
+    netmask: [u8; 4],
+  },
+}
+
+// ESP-IDF rejects hostnames longer than this.
+const MAX_HOSTNAME_LEN: usize = 32;
+
+// Hostnames must be a single DNS label: letters, digits, and hyphens, not
+// starting or ending with a hyphen.
+fn is_valid_hostname(name: &str) -> bool {
+  let bytes = name.as_bytes();
+
+  !bytes.is_empty()
+    && bytes.len() <= MAX_HOSTNAME_LEN
+    && bytes.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    && bytes[0] != b'-'
+    && bytes[bytes.len() - 1] != b'-'
+}
+
+// The firmware sends MAC addresses least-significant byte first, so use
+// from_reversed to build one from its response.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+  pub fn from_reversed(mut bytes: [u8; 6]) -> Self {
+    bytes.reverse();
+    MacAddress(bytes)
+  }
+
+  pub fn octets(&self) -> [u8; 6] {
+    self.0
+  }
+}
+
+impl core::fmt::Display for MacAddress {
+  fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let b = &self.0;
+
+    write!(
+      fmt,
+      "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+      b[0], b[1], b[2], b[3], b[4], b[5]
+    )
+  }
+}
+
+impl core::fmt::Debug for MacAddress {
+  fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    core::fmt::Display::fmt(self, fmt)
+  }
+}
+
+// A source of wall-clock time, for timestamping things.
+pub trait Clock {
+  type Error;
+
+  // Seconds since the Unix epoch.
+  fn unix_time(&mut self) -> Result<u32, Self::Error>;
+}
+
 impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
   WifiNina<CsPin, BusyPin, Spi, CountDown>
 where
@@ -40,20 +109,26 @@ where
     Ok(network_info)
   }
 
+  // Fails with Error::HostNotFound if the name couldn’t be resolved.
   pub fn resolve_host_name(
     &mut self,
     spi: &mut Spi,
     name: &str,
   ) -> Result<[u8; 4], Error<SpiError>> {
+    let mut result: u8 = 0;
     let mut ip = [0u8; 4];
 
     self.send_and_receive(
       spi,
       NinaCommand::RequestHostByName,
       Params::of(&mut [SendParam::Bytes(&mut name.bytes())]),
-      Params::of(&mut [RecvParam::Ack]),
+      Params::of(&mut [RecvParam::Byte(&mut result)]),
     )?;
 
+    if result != 1 {
+      return Err(Error::HostNotFound);
+    }
+
     self.send_and_receive(
       spi,
       NinaCommand::GetHostByName,
@@ -61,6 +136,231 @@ where
       Params::of(&mut [RecvParam::ByteArray(&mut ip)]),
     )?;
 
-    Ok(ip)
+    // Depending on how the lookup failed, the firmware reports either of these
+    // rather than an error.
+    match ip {
+      [0, 0, 0, 0] | [255, 255, 255, 255] => Err(Error::HostNotFound),
+      ip => Ok(ip),
+    }
+  }
+
+  // Configures how the chip gets its address. This needs to be called before
+  // wifi_connect or wifi_create_ap for it to take effect.
+  //
+  // The firmware treats an all-zero address as a request to use DHCP, which is
+  // also what it does by default after a reset.
+  pub fn set_ip_config(&mut self, spi: &mut Spi, config: IpConfig) -> Result<(), Error<SpiError>> {
+    let (valid_params, ip, gateway, netmask) = match config {
+      IpConfig::Dhcp => (0, [0u8; 4], [0u8; 4], [0u8; 4]),
+      IpConfig::Static {
+        ip,
+        gateway,
+        netmask,
+      } => (3, ip, gateway, netmask),
+    };
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::SetIpConfig,
+      Params::of(&mut [
+        SendParam::Byte(valid_params),
+        SendParam::Bytes(&mut ip.iter().cloned()),
+        SendParam::Bytes(&mut gateway.iter().cloned()),
+        SendParam::Bytes(&mut netmask.iter().cloned()),
+      ]),
+      Params::of(&mut [RecvParam::Ack]),
+    )
+  }
+
+  // Sets the resolvers used by resolve_host_name and for connecting to
+  // Destination::Hostname, replacing the ones that came from DHCP.
+  pub fn set_dns_servers(
+    &mut self,
+    spi: &mut Spi,
+    primary: [u8; 4],
+    secondary: Option<[u8; 4]>,
+  ) -> Result<(), Error<SpiError>> {
+    let (valid_params, secondary) = match secondary {
+      None => (1, [0u8; 4]),
+      Some(secondary) => (2, secondary),
+    };
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::SetDnsConfig,
+      Params::of(&mut [
+        SendParam::Byte(valid_params),
+        SendParam::Bytes(&mut primary.iter().cloned()),
+        SendParam::Bytes(&mut secondary.iter().cloned()),
+      ]),
+      Params::of(&mut [RecvParam::Ack]),
+    )
+  }
+
+  // Sets the name the chip reports when requesting a DHCP lease, so call it
+  // before wifi_connect.
+  pub fn set_hostname(&mut self, spi: &mut Spi, name: &str) -> Result<(), Error<SpiError>> {
+    if !is_valid_hostname(name) {
+      return Err(Error::InvalidHostname);
+    }
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::SetHostname,
+      Params::of(&mut [SendParam::Bytes(&mut name.bytes())]),
+      Params::of(&mut [RecvParam::Ack]),
+    )
+  }
+
+  pub fn mac_address(&mut self, spi: &mut Spi) -> Result<MacAddress, Error<SpiError>> {
+    let mut mac = [0u8; 6];
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::GetMacAddress,
+      Params::of(&mut [SendParam::Byte(0xFF)]),
+      Params::of(&mut [RecvParam::ByteArray(&mut mac)]),
+    )?;
+
+    Ok(MacAddress::from_reversed(mac))
+  }
+
+  // Sends an ICMP echo request and returns the round-trip time in
+  // milliseconds.
+  pub fn ping(
+    &mut self,
+    spi: &mut Spi,
+    destination: Destination,
+    ttl: u8,
+  ) -> Result<u16, Error<SpiError>> {
+    let ip = match destination {
+      Destination::Ip(ip) => ip,
+      Destination::Hostname(name) => self.resolve_host_name(spi, name)?,
+    };
+
+    let mut result: u16 = 0;
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::Ping,
+      Params::of(&mut [
+        SendParam::Bytes(&mut ip.iter().cloned()),
+        SendParam::Byte(ttl),
+      ]),
+      Params::of(&mut [RecvParam::LEWord(&mut result)]),
+    )?;
+
+    // Failures come back as negative numbers.
+    match result as i16 {
+      -1 => Err(Error::PingDestinationUnreachable),
+      -2 => Err(Error::PingTimeout),
+      -3 => Err(Error::HostNotFound),
+      r if r < 0 => Err(Error::PingFailed),
+      _ => Ok(result),
+    }
+  }
+
+  // Seconds since the Unix epoch, as kept by the firmware over SNTP. Fails
+  // with Error::TimeNotSynced until the chip has been on a network long enough
+  // to get the time.
+  pub fn network_time(&mut self, spi: &mut Spi) -> Result<u32, Error<SpiError>> {
+    let mut time = [0u8; 4];
+
+    self.send_and_receive(
+      spi,
+      NinaCommand::GetTime,
+      Params::of(&mut [SendParam::Byte(0xFF)]),
+      Params::of(&mut [RecvParam::ByteArray(&mut time)]),
+    )?;
+
+    match u32::from_le_bytes(time) {
+      0 => Err(Error::TimeNotSynced),
+      time => Ok(time),
+    }
+  }
+
+  pub fn clock<'a>(
+    &'a mut self,
+    spi: &'a mut Spi,
+  ) -> NetworkClock<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime> {
+    NetworkClock::new(spi, self)
+  }
+}
+
+// A Clock that reads the time from the firmware, for application code that
+// doesn’t need to know about the WifiNina.
+pub struct NetworkClock<'a, CS, B, S, SE, T, TC>
+where
+  CS: OutputPin,
+  B: InputPin,
+  S: FullDuplex<u8, Error = SE>
+    + embedded_hal::blocking::spi::Write<u8, Error = SE>
+    + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+  T: CountDown<Time = TC>,
+  TC: From<Milliseconds>,
+{
+  spi: &'a mut S,
+  wifi: &'a mut WifiNina<CS, B, S, T>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> NetworkClock<'a, CS, B, S, SE, T, TC>
+where
+  CS: OutputPin,
+  B: InputPin,
+  S: FullDuplex<u8, Error = SE>
+    + embedded_hal::blocking::spi::Write<u8, Error = SE>
+    + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+  T: CountDown<Time = TC>,
+  TC: From<Milliseconds>,
+{
+  pub fn new(spi: &'a mut S, wifi: &'a mut WifiNina<CS, B, S, T>) -> Self {
+    NetworkClock { spi, wifi }
+  }
+}
+
+impl<'a, CS, B, S, SE, T, TC> Clock for NetworkClock<'a, CS, B, S, SE, T, TC>
+where
+  CS: OutputPin,
+  B: InputPin,
+  S: FullDuplex<u8, Error = SE>
+    + embedded_hal::blocking::spi::Write<u8, Error = SE>
+    + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+  T: CountDown<Time = TC>,
+  TC: From<Milliseconds>,
+{
+  type Error = Error<SE>;
+
+  fn unix_time(&mut self) -> Result<u32, Self::Error> {
+    self.wifi.network_time(self.spi)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  extern crate std;
+
+  use super::{is_valid_hostname, MacAddress};
+  use std::format;
+
+  #[test]
+  fn mac_address_formats_with_colons() {
+    let mac = MacAddress::from_reversed([0x56, 0x34, 0x12, 0xcf, 0x0a, 0xa4]);
+
+    assert_eq!(mac.octets(), [0xa4, 0x0a, 0xcf, 0x12, 0x34, 0x56]);
+    assert_eq!(format!("{}", mac), "a4:0a:cf:12:34:56");
+    assert_eq!(format!("{:?}", mac), "a4:0a:cf:12:34:56");
+  }
+
+  #[test]
+  fn hostname_validation() {
+    assert!(is_valid_hostname("sensor-04"));
+    assert!(is_valid_hostname("a"));
+
+    assert!(!is_valid_hostname(""));
+    assert!(!is_valid_hostname("-sensor"));
+    assert!(!is_valid_hostname("sensor-"));
+    assert!(!is_valid_hostname("sensor.local"));
+    assert!(!is_valid_hostname("sensor_04"));
+    assert!(!is_valid_hostname("a-hostname-that-is-longer-than-32-chars"));
   }
 }
@@ -0,0 +1,145 @@
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+use crate::commands::*;
+use crate::util::millis::Milliseconds;
+use crate::{Error, WifiNina};
+
+// Values match Arduino’s pinMode constants, which the firmware passes along.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PinMode {
+    Input = 0,
+    Output = 1,
+    InputPullUp = 2,
+}
+
+impl From<PinMode> for u8 {
+    fn from(m: PinMode) -> u8 {
+        m as u8
+    }
+}
+
+// Full-scale (11dB) attenuation, so reads cover the whole 0–3.3V range.
+const ANALOG_READ_ATTENUATION: u8 = 3;
+
+// The firmware’s analog read takes an ADC1 channel rather than a GPIO number.
+fn adc1_channel(pin: u8) -> Option<u8> {
+    match pin {
+        36 => Some(0),
+        37 => Some(1),
+        38 => Some(2),
+        39 => Some(3),
+        32 => Some(4),
+        33 => Some(5),
+        34 => Some(6),
+        35 => Some(7),
+        _ => None,
+    }
+}
+
+impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
+    WifiNina<CsPin, BusyPin, Spi, CountDown>
+where
+    BusyPin: InputPin,
+    CsPin: OutputPin,
+    Spi: FullDuplex<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::Write<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SpiError>,
+    CountDown: embedded_hal::timer::CountDown<Time = CountDownTime>,
+    CountDownTime: From<Milliseconds>,
+{
+    pub fn pin_mode(
+        &mut self,
+        spi: &mut Spi,
+        pin: u8,
+        mode: PinMode,
+    ) -> Result<(), Error<SpiError>> {
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetPinMode,
+            Params::of(&mut [SendParam::Byte(pin), SendParam::Byte(mode.into())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )
+    }
+
+    pub fn pin_digital_write(
+        &mut self,
+        spi: &mut Spi,
+        pin: u8,
+        high: bool,
+    ) -> Result<(), Error<SpiError>> {
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetDigitalWrite,
+            Params::of(&mut [SendParam::Byte(pin), SendParam::Byte(high as u8)]),
+            Params::of(&mut [RecvParam::Ack]),
+        )
+    }
+
+    // Drives the pin with a PWM signal, where 255 is always on.
+    pub fn pin_analog_write(
+        &mut self,
+        spi: &mut Spi,
+        pin: u8,
+        value: u8,
+    ) -> Result<(), Error<SpiError>> {
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetAnalogWrite,
+            Params::of(&mut [SendParam::Byte(pin), SendParam::Byte(value)]),
+            Params::of(&mut [RecvParam::Ack]),
+        )
+    }
+
+    pub fn pin_digital_read(&mut self, spi: &mut Spi, pin: u8) -> Result<bool, Error<SpiError>> {
+        let mut value: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetDigitalRead,
+            Params::of(&mut [SendParam::Byte(pin)]),
+            Params::of(&mut [RecvParam::Byte(&mut value)]),
+        )?;
+
+        Ok(value != 0)
+    }
+
+    // Reads the raw 12-bit ADC value for the pin. Only the ESP32’s ADC1 pins
+    // (32–39) can be read.
+    pub fn pin_analog_read(&mut self, spi: &mut Spi, pin: u8) -> Result<u16, Error<SpiError>> {
+        let channel = adc1_channel(pin).ok_or(Error::InvalidPin(pin))?;
+
+        let mut value = [0u8; 4];
+        let mut len: usize = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetAnalogRead,
+            Params::of(&mut [
+                SendParam::Byte(channel),
+                SendParam::Byte(ANALOG_READ_ATTENUATION),
+            ]),
+            Params::of(&mut [RecvParam::Buffer(&mut value, &mut len)]),
+        )?;
+
+        // The value is little-endian, and we only care about the low bits.
+        Ok(u16::from_le_bytes([value[0], value[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::adc1_channel;
+
+    #[test]
+    fn adc1_channels_match_gpios() {
+        // Arduino’s A4–A7 on the Nano 33 IoT.
+        assert_eq!(adc1_channel(39), Some(3));
+        assert_eq!(adc1_channel(34), Some(6));
+        assert_eq!(adc1_channel(36), Some(0));
+        assert_eq!(adc1_channel(35), Some(7));
+
+        assert_eq!(adc1_channel(25), None);
+    }
+}
@@ -13,6 +13,9 @@ use crate::util::millis::{Milliseconds, U32Ext};
 use crate::{Error, WifiNina};
 use crate::commands::*;
 
+// The firmware hands out socket numbers below this.
+const MAX_SOCKETS: u8 = 10;
+
 impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
     WifiNina<CsPin, BusyPin, Spi, CountDown>
 where
@@ -100,7 +103,7 @@ where
             )?,
         }
 
-        if let None = result {
+        if result.is_none() {
             return Err(Error::SocketConnectionFailed(SocketStatus::UnknownStatus));
         }
 
@@ -108,7 +111,7 @@ where
 
         // Wait 3 seconds for the connection.
         for _ in 0..300 {
-            last_status = self.socket_status(spi, &socket)?;
+            last_status = self.socket_status(spi, socket)?;
 
             if last_status == SocketStatus::Established {
                 return Ok(SocketStatus::Established);
@@ -139,6 +142,14 @@ where
         )
     }
 
+    // Closes every socket on the chip, including ones this driver has lost
+    // track of. Errors closing individual sockets are ignored.
+    pub fn socket_close_all(&mut self, spi: &mut Spi) {
+        for num in 0..MAX_SOCKETS {
+            self.socket_close(spi, &Socket::new(num)).ok();
+        }
+    }
+
     pub fn connect<'a>(
         &'a mut self,
         spi: &'a mut Spi,
@@ -156,6 +167,143 @@ where
         Ok(ConnectedSocket::new(spi, self, socket))
     }
 
+    // Starts a server on the socket. For TCP, the firmware accepts incoming
+    // connections in the background, which are picked up with server_accept.
+    pub fn server_start(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+        protocol: Protocol,
+        port: u16,
+    ) -> Result<(), Error<SpiError>> {
+        let mut result: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::StartServerTcp,
+            Params::of(&mut [
+                SendParam::Word(port),
+                SendParam::Byte(socket.num()),
+                SendParam::Byte(protocol.into()),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::SocketListenFailed(SocketStatus::UnknownStatus));
+        }
+
+        Ok(())
+    }
+
+    // Starts a UDP server that receives datagrams sent to the given multicast
+    // group address.
+    pub fn server_start_multicast(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+        group: [u8; 4],
+        port: u16,
+    ) -> Result<(), Error<SpiError>> {
+        let mut result: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::StartServerTcp,
+            Params::of(&mut [
+                SendParam::Bytes(&mut group.iter().cloned()),
+                SendParam::Word(port),
+                SendParam::Byte(socket.num()),
+                SendParam::Byte(Protocol::UDPMulticast.into()),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::SocketListenFailed(SocketStatus::UnknownStatus));
+        }
+
+        Ok(())
+    }
+
+    pub fn server_status(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+    ) -> Result<SocketStatus, Error<SpiError>> {
+        let mut status: u8 = 255;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetStateTcp,
+            Params::of(&mut [SendParam::Byte(socket.num())]),
+            Params::of(&mut [RecvParam::Byte(&mut status)]),
+        )?;
+
+        Ok(status.into())
+    }
+
+    // Returns the socket for the next client that has connected to the
+    // server, or WouldBlock if there isn’t one waiting.
+    //
+    // Like socket_new, the returned Socket’s lifetime isn’t tied to self/spi.
+    pub fn server_accept<'b>(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+    ) -> Result<Socket<'b, CsPin, Spi>, nb::Error<Error<SpiError>>> {
+        // For server sockets, the firmware responds to this command with the
+        // number of a newly-connected client socket rather than a byte count.
+        let mut client_num: u16 = 255;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::AvailableDataTcp,
+            Params::of(&mut [SendParam::Byte(socket.num())]),
+            Params::of(&mut [RecvParam::LEWord(&mut client_num)]),
+        )
+        .map_err(nb::Error::Other)?;
+
+        if client_num == 255 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(Socket::new(client_num as u8))
+    }
+
+    pub fn server_listen<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+        port: u16,
+    ) -> Result<
+        ServerSocket<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>,
+        Error<SpiError>,
+    > {
+        let socket = self.socket_new(spi)?;
+
+        // Constructing the ServerSocket first means that the socket gets closed
+        // on drop if anything below fails.
+        let server = ServerSocket::new(spi, self, socket);
+
+        server.wifi.server_start(server.spi, &server.socket, Protocol::TCP, port)?;
+
+        let mut last_status = SocketStatus::UnknownStatus;
+
+        // Wait 1 second for the server to start listening.
+        for _ in 0..100 {
+            last_status = server.wifi.server_status(server.spi, &server.socket)?;
+
+            if last_status == SocketStatus::Listen {
+                return Ok(server);
+            }
+
+            server.wifi.timer.start(10.ms());
+            nb::block!(server.wifi.timer.wait()).ok();
+        }
+
+        Err(Error::SocketListenFailed(last_status))
+    }
+
     pub fn socket_write(
         &mut self,
         spi: &mut Spi,
@@ -184,6 +332,163 @@ where
         socket: &Socket<CsPin, Spi>,
         buf: &mut [u8],
     ) -> Result<usize, nb::Error<Error<SpiError>>> {
+        let available = self.socket_available(spi, socket)?;
+
+        if available == 0 {
+            return match self.socket_status(spi, socket)? {
+                SocketStatus::Closed => Ok(0),
+                _ => Err(nb::Error::WouldBlock),
+            };
+        }
+
+        Ok(self.socket_read_available(spi, socket, available, buf)?)
+    }
+
+    // Sends a single datagram to the given address from a UDP socket.
+    //
+    // The firmware builds up the packet in three steps: starting it with the
+    // destination, copying in the data, and then sending it.
+    pub fn udp_send_to(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+        ip: [u8; 4],
+        port: u16,
+        bytes: &mut dyn ExactSizeIterator<Item = u8>,
+    ) -> Result<usize, Error<SpiError>> {
+        let len = bytes.len();
+        let mut result: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::StartClientTcp,
+            Params::of(&mut [
+                SendParam::Bytes(&mut ip.iter().cloned()),
+                SendParam::Word(port),
+                SendParam::Byte(socket.num()),
+                SendParam::Byte(Protocol::UDP.into()),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::UdpSendFailed);
+        }
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::InsertDatabuf,
+            Params::with_16_bit_length(&mut [
+                SendParam::Byte(socket.num()),
+                SendParam::Bytes(bytes),
+            ]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::UdpSendFailed);
+        }
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::SendDataUdp,
+            Params::of(&mut [SendParam::Byte(socket.num())]),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::UdpSendFailed);
+        }
+
+        Ok(len)
+    }
+
+    // Reads the next datagram that arrived on a UDP socket, returning its
+    // length along with the sender’s address and port.
+    //
+    // If buf is smaller than the datagram, the rest of it is discarded.
+    pub fn udp_recv_from(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+        buf: &mut [u8],
+    ) -> Result<(usize, [u8; 4], u16), nb::Error<Error<SpiError>>> {
+        // For UDP sockets, this also has the firmware parse the next packet if
+        // it’s finished with the previous one.
+        let available = self.socket_available(spi, socket)?;
+
+        if available == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let mut ip = [0u8; 4];
+        let mut port: u16 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetRemoteData,
+            Params::of(&mut [SendParam::Byte(socket.num())]),
+            Params::of(&mut [RecvParam::ByteArray(&mut ip), RecvParam::Word(&mut port)]),
+        )?;
+
+        let read = self.socket_read_available(spi, socket, available, buf)?;
+
+        // The firmware would otherwise hand back what’s left as though it were
+        // the next datagram.
+        let mut remaining = available as usize - read;
+        let mut discard = [0u8; 64];
+
+        while remaining > 0 {
+            match self.socket_read_available(spi, socket, remaining as u16, &mut discard)? {
+                0 => break,
+                n => remaining -= n,
+            }
+        }
+
+        Ok((read, ip, port))
+    }
+
+    pub fn udp_bind<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+        port: u16,
+    ) -> Result<
+        UdpSocket<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>,
+        Error<SpiError>,
+    > {
+        let socket = self.socket_new(spi)?;
+        let udp = UdpSocket::new(spi, self, socket);
+
+        udp.wifi.server_start(udp.spi, &udp.socket, Protocol::UDP, port)?;
+
+        Ok(udp)
+    }
+
+    // Joins the multicast group and listens on port. Datagrams sent to the
+    // group are read with recv_from, which also reports who sent them.
+    pub fn udp_bind_multicast<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+        group: [u8; 4],
+        port: u16,
+    ) -> Result<
+        UdpSocket<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>,
+        Error<SpiError>,
+    > {
+        let socket = self.socket_new(spi)?;
+        let udp = UdpSocket::new(spi, self, socket);
+
+        udp.wifi
+            .server_start_multicast(udp.spi, &udp.socket, group, port)?;
+
+        Ok(udp)
+    }
+
+    fn socket_available(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+    ) -> Result<u16, Error<SpiError>> {
         let mut available: u16 = 0;
 
         self.send_and_receive(
@@ -191,16 +496,18 @@ where
             NinaCommand::AvailableDataTcp,
             Params::of(&mut [SendParam::Byte(socket.num())]),
             Params::of(&mut [RecvParam::LEWord(&mut available)]),
-        )
-        .map_err(|err| nb::Error::Other(err))?;
+        )?;
 
-        if available == 0 {
-            return match self.socket_status(spi, socket)? {
-                SocketStatus::Closed => Ok(0),
-                _ => Err(nb::Error::WouldBlock),
-            };
-        }
+        Ok(available)
+    }
 
+    fn socket_read_available(
+        &mut self,
+        spi: &mut Spi,
+        socket: &Socket<CsPin, Spi>,
+        available: u16,
+        buf: &mut [u8],
+    ) -> Result<usize, Error<SpiError>> {
         let req_size = core::cmp::min(available, buf.len() as u16);
 
         let mut read: usize = 0;
@@ -213,10 +520,7 @@ where
                 SendParam::LEWord(req_size),
             ]),
             Params::with_16_bit_length(&mut [RecvParam::Buffer(buf, &mut read)]),
-        )
-        .map_err(|err| {
-            return nb::Error::Other(err);
-        })?;
+        )?;
 
         Ok(read)
     }
@@ -262,10 +566,11 @@ pub enum Protocol {
     TCP = 0,
     UDP = 1,
     TLS = 2,
+    UDPMulticast = 3,
 }
-impl Into<u8> for Protocol {
-    fn into(self) -> u8 {
-        self as u8
+impl From<Protocol> for u8 {
+    fn from(p: Protocol) -> u8 {
+        p as u8
     }
 }
 
@@ -370,6 +675,127 @@ where
     }
 }
 
+pub struct ServerSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    spi: &'a mut S,
+    wifi: &'a mut WifiNina<CS, B, S, T>,
+    socket: Socket<'a, CS, S>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> ServerSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn new(
+        spi: &'a mut S,
+        wifi: &'a mut WifiNina<CS, B, S, T>,
+        socket: Socket<'a, CS, S>,
+    ) -> Self {
+        ServerSocket { spi, wifi, socket }
+    }
+
+    // Hands back a connection to the next waiting client. The returned socket
+    // borrows the server, so only one client can be handled at a time. The
+    // client’s connection is closed when it is dropped.
+    pub fn accept(
+        &mut self,
+    ) -> Result<ConnectedSocket<'_, CS, B, S, SE, T, TC>, nb::Error<Error<SE>>> {
+        let client = self.wifi.server_accept(self.spi, &self.socket)?;
+
+        Ok(ConnectedSocket::new(self.spi, self.wifi, client))
+    }
+}
+
+impl<'a, CS, B, S, SE, T, TC> Drop for ServerSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    fn drop(&mut self) {
+        self.wifi.socket_close(self.spi, &self.socket).ok();
+    }
+}
+
+pub struct UdpSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    spi: &'a mut S,
+    wifi: &'a mut WifiNina<CS, B, S, T>,
+    socket: Socket<'a, CS, S>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> UdpSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn new(
+        spi: &'a mut S,
+        wifi: &'a mut WifiNina<CS, B, S, T>,
+        socket: Socket<'a, CS, S>,
+    ) -> Self {
+        UdpSocket { spi, wifi, socket }
+    }
+
+    pub fn send_to(&mut self, ip: [u8; 4], port: u16, buf: &[u8]) -> Result<usize, Error<SE>> {
+        self.wifi
+            .udp_send_to(self.spi, &self.socket, ip, port, &mut buf.iter().cloned())
+    }
+
+    pub fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<(usize, [u8; 4], u16), nb::Error<Error<SE>>> {
+        self.wifi.udp_recv_from(self.spi, &self.socket, buf)
+    }
+}
+
+impl<'a, CS, B, S, SE, T, TC> Drop for UdpSocket<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    fn drop(&mut self) {
+        self.wifi.socket_close(self.spi, &self.socket).ok();
+    }
+}
+
 impl<'a, CS, B, S, SE, T, TC> core::fmt::Write for ConnectedSocket<'a, CS, B, S, SE, T, TC>
 where
     CS: OutputPin,
@@ -3,6 +3,7 @@ use embedded_hal::spi::FullDuplex;
 
 use nb::block;
 
+use crate::commands::network::MacAddress;
 use crate::commands::*;
 use crate::util::millis::{Milliseconds, U32Ext};
 use crate::{Error, WifiNina};
@@ -43,6 +44,121 @@ impl From<u8> for WifiStatus {
     }
 }
 
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum EncryptionType {
+    Tkip = 2,
+    Ccmp = 4,
+    Wep = 5,
+    None = 7,
+    Auto = 8,
+
+    #[default]
+    UnknownType = 255,
+}
+
+impl From<u8> for EncryptionType {
+    fn from(e: u8) -> Self {
+        match e {
+            2 => EncryptionType::Tkip,
+            4 => EncryptionType::Ccmp,
+            5 => EncryptionType::Wep,
+            7 => EncryptionType::None,
+            8 => EncryptionType::Auto,
+
+            _ => EncryptionType::UnknownType,
+        }
+    }
+}
+
+// The firmware only has these two levels: any non-zero mode turns on modem
+// sleep.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PowerMode {
+    // Radio stays awake at all times.
+    None = 0,
+    // Radio sleeps between beacons, waking at the access point’s default
+    // listen interval.
+    ModemSleep = 1,
+}
+
+impl From<PowerMode> for u8 {
+    fn from(m: PowerMode) -> u8 {
+        m as u8
+    }
+}
+
+// Credentials for WPA2-Enterprise (PEAP/MSCHAPv2) networks. The identity is
+// the outer, unencrypted identity, which is often the same as the username or
+// "anonymous".
+#[derive(Debug, Clone, Copy)]
+pub struct EnterpriseCredentials<'a> {
+    pub identity: &'a str,
+    pub username: &'a str,
+    pub password: &'a str,
+}
+
+// SSIDs are at most 32 bytes, and aren’t required to be UTF-8.
+#[derive(Clone, Copy, Default)]
+pub struct Ssid {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl Ssid {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.as_bytes())
+    }
+}
+
+impl core::fmt::Debug for Ssid {
+    fn fmt(
+        &self,
+        fmt: &mut core::fmt::Formatter<'_>,
+    ) -> core::result::Result<(), core::fmt::Error> {
+        match self.as_str() {
+            Ok(s) => write!(fmt, "{:?}", s),
+            Err(_) => write!(fmt, "{:?}", self.as_bytes()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanResult {
+    pub ssid: Ssid,
+    pub rssi: i32,
+    pub encryption: EncryptionType,
+    pub bssid: MacAddress,
+    pub channel: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectionInfo {
+    pub ssid: Ssid,
+    pub bssid: MacAddress,
+    pub rssi: i32,
+    pub encryption: EncryptionType,
+}
+
+// WPA2 passphrases are 8 to 63 printable ASCII characters.
+fn is_valid_passphrase(passphrase: &str) -> bool {
+    (8..=63).contains(&passphrase.len())
+        && passphrase.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+// WEP keys are given as hex, 10 digits for 40-bit keys or 26 for 104-bit.
+fn is_valid_wep_key(key: &str) -> bool {
+    (key.len() == 10 || key.len() == 26) && key.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// The firmware only keeps this many networks from a scan.
+const MAX_SCAN_RESULTS: usize = 10;
+
 impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
     WifiNina<CsPin, BusyPin, Spi, CountDown>
 where
@@ -67,6 +183,74 @@ where
         Ok(status.into())
     }
 
+    // Details about the network we’re currently associated with.
+    pub fn connection_info(&mut self, spi: &mut Spi) -> Result<ConnectionInfo, Error<SpiError>> {
+        let mut info: ConnectionInfo = Default::default();
+        let mut bssid = [0u8; 6];
+        let mut rssi = [0u8; 4];
+        let mut encryption: u8 = 255;
+
+        // These commands ignore their parameter, but the firmware expects one
+        // to be sent.
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetCurrentSsid,
+            Params::of(&mut [SendParam::Byte(0xFF)]),
+            Params::of(&mut [RecvParam::Buffer(&mut info.ssid.bytes, &mut info.ssid.len)]),
+        )?;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetCurrentBssid,
+            Params::of(&mut [SendParam::Byte(0xFF)]),
+            Params::of(&mut [RecvParam::ByteArray(&mut bssid)]),
+        )?;
+
+        info.bssid = MacAddress::from_reversed(bssid);
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetCurrentRssi,
+            Params::of(&mut [SendParam::Byte(0xFF)]),
+            Params::of(&mut [RecvParam::ByteArray(&mut rssi)]),
+        )?;
+
+        info.rssi = i32::from_le_bytes(rssi);
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::GetCurrentEnct,
+            Params::of(&mut [SendParam::Byte(0xFF)]),
+            Params::of(&mut [RecvParam::Byte(&mut encryption)]),
+        )?;
+
+        info.encryption = encryption.into();
+
+        Ok(info)
+    }
+
+    pub fn set_power_mode(
+        &mut self,
+        spi: &mut Spi,
+        mode: PowerMode,
+    ) -> Result<(), Error<SpiError>> {
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetPowerMode,
+            Params::of(&mut [SendParam::Byte(mode.into())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.power_mode = mode;
+
+        Ok(())
+    }
+
+    // The mode last applied with set_power_mode since the chip was reset.
+    pub fn power_mode(&self) -> PowerMode {
+        self.power_mode
+    }
+
     pub fn wifi_connect(
         &mut self,
         spi: &mut Spi,
@@ -96,21 +280,94 @@ where
             }
         }
 
-        let mut last_status = WifiStatus::UnknownStatus;
+        self.wifi_wait_for_status(spi, &[WifiStatus::Connected], &[])
+    }
 
-        // Wait 10 seconds for the Wifi to stabilize.
-        for _ in 0..10 {
-            last_status = self.wifi_status(spi)?;
+    // Joins a legacy WEP network. key_index is the slot (0–3) the access point
+    // uses for key.
+    pub fn wifi_connect_wep(
+        &mut self,
+        spi: &mut Spi,
+        ssid: &str,
+        key_index: u8,
+        key: &str,
+    ) -> Result<WifiStatus, Error<SpiError>> {
+        if key_index > 3 || !is_valid_wep_key(key) {
+            return Err(Error::InvalidWepKey);
+        }
 
-            if last_status == WifiStatus::Connected {
-                return Ok(last_status);
-            }
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetKey,
+            Params::of(&mut [
+                SendParam::Bytes(&mut ssid.bytes()),
+                SendParam::Byte(key_index),
+                SendParam::Bytes(&mut key.bytes()),
+            ]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
 
-            self.timer.start(1_000.ms());
-            block!(self.timer.wait()).ok();
-        }
+        self.wifi_wait_for_status(spi, &[WifiStatus::Connected], &[])
+    }
 
-        Err(Error::ConnectionFailed(last_status))
+    pub fn wifi_connect_enterprise(
+        &mut self,
+        spi: &mut Spi,
+        ssid: &str,
+        credentials: &EnterpriseCredentials,
+    ) -> Result<WifiStatus, Error<SpiError>> {
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetEnterpriseIdent,
+            Params::of(&mut [SendParam::Bytes(&mut credentials.identity.bytes())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetEnterpriseUsername,
+            Params::of(&mut [SendParam::Bytes(&mut credentials.username.bytes())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetEnterprisePassword,
+            Params::of(&mut [SendParam::Bytes(&mut credentials.password.bytes())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetEnterpriseEnable,
+            Params::none(),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        // With enterprise mode enabled, joining is the same as for an open
+        // network.
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetNetwork,
+            Params::of(&mut [SendParam::Bytes(&mut ssid.bytes())]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.wifi_wait_for_status(spi, &[WifiStatus::Connected], &[])
+    }
+
+    // Leaves the current network, closing any open sockets first.
+    pub fn wifi_disconnect(&mut self, spi: &mut Spi) -> Result<WifiStatus, Error<SpiError>> {
+        self.socket_close_all(spi);
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::Disconnect,
+            Params::of(&mut [SendParam::Byte(0xFF)]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.wifi_wait_for_status(spi, &[WifiStatus::Disconnected, WifiStatus::Idle], &[])
     }
 
     pub fn wifi_create_ap(
@@ -129,4 +386,179 @@ where
             Params::of(&mut [RecvParam::Ack]),
         )
     }
+
+    // Creates a WPA2-protected access point and waits for it to start
+    // listening.
+    pub fn wifi_create_ap_with_passphrase(
+        &mut self,
+        spi: &mut Spi,
+        name: &str,
+        passphrase: &str,
+        channel: u8,
+    ) -> Result<WifiStatus, Error<SpiError>> {
+        if !is_valid_passphrase(passphrase) {
+            return Err(Error::InvalidPassphrase);
+        }
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::SetApPassphrase,
+            Params::of(&mut [
+                SendParam::Bytes(&mut name.bytes()),
+                SendParam::Bytes(&mut passphrase.bytes()),
+                SendParam::Byte(channel),
+            ]),
+            Params::of(&mut [RecvParam::Ack]),
+        )?;
+
+        self.wifi_wait_for_status(
+            spi,
+            &[WifiStatus::ApListening, WifiStatus::ApConnected],
+            &[WifiStatus::ApFailed],
+        )
+    }
+
+    // Polls the status until it’s one of targets, giving up early if it’s one
+    // of failures.
+    fn wifi_wait_for_status(
+        &mut self,
+        spi: &mut Spi,
+        targets: &[WifiStatus],
+        failures: &[WifiStatus],
+    ) -> Result<WifiStatus, Error<SpiError>> {
+        let mut last_status = WifiStatus::UnknownStatus;
+
+        // Wait 10 seconds for the Wifi to stabilize.
+        for _ in 0..10 {
+            last_status = self.wifi_status(spi)?;
+
+            if targets.contains(&last_status) {
+                return Ok(last_status);
+            }
+
+            if failures.contains(&last_status) {
+                break;
+            }
+
+            self.timer.start(1_000.ms());
+            block!(self.timer.wait()).ok();
+        }
+
+        Err(Error::ConnectionFailed(last_status))
+    }
+
+    // Scans for nearby networks, filling in results and returning how many
+    // were found (capped at the length of results).
+    pub fn scan_networks(
+        &mut self,
+        spi: &mut Spi,
+        results: &mut [ScanResult],
+    ) -> Result<usize, Error<SpiError>> {
+        let mut result: u8 = 0;
+
+        self.send_and_receive(
+            spi,
+            NinaCommand::StartScanNetworks,
+            Params::none(),
+            Params::of(&mut [RecvParam::Byte(&mut result)]),
+        )?;
+
+        if result == 0 {
+            return Err(Error::ScanFailed);
+        }
+
+        let mut ssids = [[0u8; 32]; MAX_SCAN_RESULTS];
+        let mut ssid_lens: [Option<usize>; MAX_SCAN_RESULTS] = [None; MAX_SCAN_RESULTS];
+        let mut count = 0;
+
+        // The scan takes a few seconds, during which the firmware reports no
+        // networks. Wait up to 10 seconds for some to show up.
+        for _ in 0..10 {
+            self.timer.start(1_000.ms());
+            block!(self.timer.wait()).ok();
+
+            let mut ssid_iter = ssids.iter_mut().zip(ssid_lens.iter_mut());
+            let mut params: [RecvParam; MAX_SCAN_RESULTS] = core::array::from_fn(|_| {
+                let (ssid, len) = ssid_iter.next().unwrap();
+                RecvParam::OptionalBuffer(ssid, len)
+            });
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::ScanNetworks,
+                Params::none(),
+                Params::of(&mut params),
+            )?;
+
+            count = ssid_lens.iter().take_while(|len| len.is_some()).count();
+
+            if count > 0 {
+                break;
+            }
+        }
+
+        let count = core::cmp::min(count, results.len());
+
+        for idx in 0..count {
+            let result = &mut results[idx];
+            let mut bssid = [0u8; 6];
+            let mut rssi = [0u8; 4];
+            let mut encryption: u8 = 255;
+
+            let len = ssid_lens[idx].unwrap_or(0);
+            result.ssid.bytes[..len].copy_from_slice(&ssids[idx][..len]);
+            result.ssid.len = len;
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::GetIdxRssi,
+                Params::of(&mut [SendParam::Byte(idx as u8)]),
+                Params::of(&mut [RecvParam::ByteArray(&mut rssi)]),
+            )?;
+
+            result.rssi = i32::from_le_bytes(rssi);
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::GetIdxEnct,
+                Params::of(&mut [SendParam::Byte(idx as u8)]),
+                Params::of(&mut [RecvParam::Byte(&mut encryption)]),
+            )?;
+
+            result.encryption = encryption.into();
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::GetIdxBssid,
+                Params::of(&mut [SendParam::Byte(idx as u8)]),
+                Params::of(&mut [RecvParam::ByteArray(&mut bssid)]),
+            )?;
+
+            result.bssid = MacAddress::from_reversed(bssid);
+
+            self.send_and_receive(
+                spi,
+                NinaCommand::GetIdxChannel,
+                Params::of(&mut [SendParam::Byte(idx as u8)]),
+                Params::of(&mut [RecvParam::Byte(&mut result.channel)]),
+            )?;
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_wep_key;
+
+    #[test]
+    fn wep_key_validation() {
+        assert!(is_valid_wep_key("0123456789"));
+        assert!(is_valid_wep_key("0123456789abcdefABCDEF0123"));
+
+        assert!(!is_valid_wep_key("012345678"));
+        assert!(!is_valid_wep_key("01234567890"));
+        assert!(!is_valid_wep_key("012345678g"));
+    }
 }
@@ -1,8 +1,15 @@
 #![no_std]
+// The generic parameters we thread through for the SPI bus, pins, and timer
+// make socket return types long no matter how they’re spelled.
+#![allow(clippy::type_complexity)]
 
 mod chip_select;
 mod util;
 pub mod commands;
+pub mod nina_flasher;
+pub mod nina_pins;
+#[cfg(feature = "embedded-nal-traits")]
+pub mod nal;
 
 use embedded_hal::digital::v2::{InputPin, OutputPin};
 use embedded_hal::spi::FullDuplex;
@@ -13,7 +20,7 @@ use util::millis::{Milliseconds, U32Ext};
 
 use chip_select::*;
 
-use commands::{socket::SocketStatus, wifi::WifiStatus,};
+use commands::{firmware::FirmwareVersion, socket::SocketStatus, wifi::{PowerMode, WifiStatus},};
 
 
 pub struct WifiNina<CsPin, BusyPin, Spi, CountDown>
@@ -24,6 +31,7 @@ where
     spi: core::marker::PhantomData<Spi>,
     chip_select: WifiNinaChipSelect<Spi, CsPin, BusyPin>,
     timer: CountDown,
+    power_mode: PowerMode,
 }
 
 impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
@@ -39,15 +47,18 @@ where
 {
     // const ConnectionDelayMs: u16 = 100;
 
-    // We take the spi here just to allow the type to be implied.
-    //
     // Also resets the WifiNINA chip.
+    //
+    // If min_firmware_version is provided, the firmware version is read after
+    // the reset and this fails with Error::FirmwareTooOld if it’s older.
+    // Otherwise the spi is only used to allow its type to be implied.
     pub fn new<ResetPin>(
-        _spi: &Spi,
+        spi: &mut Spi,
         cs: CsPin,
         busy: BusyPin,
         reset: &mut ResetPin,
         timer: CountDown,
+        min_firmware_version: Option<FirmwareVersion>,
     ) -> Result<Self, Error<SpiError>>
     where
         ResetPin: OutputPin,
@@ -57,10 +68,19 @@ where
             chip_select: WifiNinaChipSelect::new(cs, busy)
                 .map_err(|_| Error::ChipSelectPinError)?,
             timer,
+            power_mode: PowerMode::None,
         };
 
         wifi.reset(reset)?;
 
+        if let Some(min_version) = min_firmware_version {
+            let version = wifi.firmware_version(spi)?;
+
+            if version < min_version {
+                return Err(Error::FirmwareTooOld(version));
+            }
+        }
+
         Ok(wifi)
     }
 
@@ -78,6 +98,39 @@ where
         self.timer.start(750.ms());
         block!(self.timer.wait()).unwrap();
 
+        // The firmware starts up with power saving off.
+        self.power_mode = PowerMode::None;
+
+        Ok(())
+    }
+
+    // Resets the ESP32 into its ROM serial bootloader by holding GPIO0 low as
+    // it comes out of reset, for use with nina_flasher. The chip won’t respond
+    // to SPI commands until it’s reset again with reset.
+    pub fn reset_to_bootloader<ResetPin, BootPin>(
+        &mut self,
+        reset: &mut ResetPin,
+        gpio0: &mut BootPin,
+    ) -> Result<(), Error<SpiError>>
+    where
+        ResetPin: OutputPin,
+        BootPin: OutputPin,
+    {
+        gpio0.set_low().map_err(|_| Error::BootPinError)?;
+        reset.set_low().map_err(|_| Error::ResetPinError)?;
+
+        self.timer.start(200.ms());
+        block!(self.timer.wait()).unwrap();
+
+        reset.set_high().map_err(|_| Error::ResetPinError)?;
+
+        // GPIO0 is only sampled at boot, so it can be released once the
+        // bootloader has started.
+        self.timer.start(100.ms());
+        block!(self.timer.wait()).unwrap();
+
+        gpio0.set_high().map_err(|_| Error::BootPinError)?;
+
         Ok(())
     }
 
@@ -96,16 +149,44 @@ pub enum Error<SpiError> {
     ErrorResponse,
     UnexpectedResponse(u8, u8),
 
+    InvalidFirmwareVersion,
+    FirmwareTooOld(FirmwareVersion),
+
     ConnectionFailed(WifiStatus),
     ConnectionTimeout,
+    InvalidPassphrase,
+    InvalidWepKey,
+    InvalidHostname,
+    ScanFailed,
 
     SocketConnectionFailed(SocketStatus),
+    SocketListenFailed(SocketStatus),
     SocketClosed,
     SocketTimeout,
     NoSocketAvailable,
+    SocketNotConnected,
+    AddressNotSupported,
+    InvalidCertificate,
+    CertificateTooLarge,
+    UdpSendFailed,
+
+    HostNotFound,
+    PingDestinationUnreachable,
+    PingTimeout,
+    PingFailed,
+
+    TimeNotSynced,
+
+    FileOperationFailed,
+    InvalidPath,
+    OtaDownloadFailed,
 
     SpiError(SpiError),
     ResetPinError,
+    BootPinError,
+
+    InvalidPin(u8),
+    PinInUse(u8),
 }
 
 impl<SpiError> Error<SpiError> {
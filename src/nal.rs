@@ -0,0 +1,313 @@
+// Implementations of the embedded-nal network traits, so that crates written
+// against them can use the WifiNina.
+//
+// The traits’ methods only take &mut self, so they’re implemented on NinaStack,
+// which bundles the WifiNina with the SPI bus it talks over.
+
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+use embedded_hal::timer::CountDown;
+
+use embedded_nal::{
+    AddrType, Dns, TcpClientStack, TcpError, TcpErrorKind, UdpClientStack, UdpFullStack,
+};
+
+use crate::commands::socket::{Destination, Protocol, Socket, SocketStatus};
+use crate::util::millis::Milliseconds;
+use crate::{Error, WifiNina};
+
+impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
+    WifiNina<CsPin, BusyPin, Spi, CountDown>
+where
+    BusyPin: InputPin,
+    CsPin: OutputPin,
+    Spi: FullDuplex<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::Write<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SpiError>,
+    CountDown: embedded_hal::timer::CountDown<Time = CountDownTime>,
+    CountDownTime: From<Milliseconds>,
+{
+    pub fn stack<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+    ) -> NinaStack<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime> {
+        NinaStack::new(spi, self)
+    }
+}
+
+pub struct NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    spi: &'a mut S,
+    wifi: &'a mut WifiNina<CS, B, S, T>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn new(spi: &'a mut S, wifi: &'a mut WifiNina<CS, B, S, T>) -> Self {
+        NinaStack { spi, wifi }
+    }
+}
+
+// The firmware only speaks IPv4.
+fn ipv4_octets<SE>(addr: &SocketAddr) -> Result<[u8; 4], Error<SE>> {
+    match addr {
+        SocketAddr::V4(addr) => Ok(addr.ip().octets()),
+        SocketAddr::V6(_) => Err(Error::AddressNotSupported),
+    }
+}
+
+fn socket_addr(ip: [u8; 4], port: u16) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip)), port)
+}
+
+impl<SE: core::fmt::Debug> TcpError for Error<SE> {
+    fn kind(&self) -> TcpErrorKind {
+        match self {
+            Error::SocketClosed => TcpErrorKind::PipeClosed,
+            _ => TcpErrorKind::Other,
+        }
+    }
+}
+
+impl<'a, CS, B, S, SE, T, TC> TcpClientStack for NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+    SE: core::fmt::Debug,
+{
+    type TcpSocket = Socket<'a, CS, S>;
+    type Error = Error<SE>;
+
+    fn socket(&mut self) -> Result<Self::TcpSocket, Self::Error> {
+        self.wifi.socket_new(self.spi)
+    }
+
+    // Blocks until the connection is established or fails, rather than
+    // returning WouldBlock.
+    fn connect(
+        &mut self,
+        socket: &mut Self::TcpSocket,
+        remote: SocketAddr,
+    ) -> nb::Result<(), Self::Error> {
+        let ip = ipv4_octets(&remote)?;
+
+        self.wifi.socket_open(
+            self.spi,
+            socket,
+            Protocol::TCP,
+            Destination::Ip(ip),
+            remote.port(),
+        )?;
+
+        Ok(())
+    }
+
+    fn send(
+        &mut self,
+        socket: &mut Self::TcpSocket,
+        buffer: &[u8],
+    ) -> nb::Result<usize, Self::Error> {
+        let written = self
+            .wifi
+            .socket_write(self.spi, socket, &mut buffer.iter().cloned())?;
+
+        if written == 0 && !buffer.is_empty() {
+            return match self.wifi.socket_status(self.spi, socket)? {
+                SocketStatus::Closed => Err(nb::Error::Other(Error::SocketClosed)),
+                _ => Err(nb::Error::WouldBlock),
+            };
+        }
+
+        Ok(written)
+    }
+
+    // socket_read reports a closed socket as reading nothing, which clients of
+    // these traits would keep polling, so it’s turned into an error here.
+    fn receive(
+        &mut self,
+        socket: &mut Self::TcpSocket,
+        buffer: &mut [u8],
+    ) -> nb::Result<usize, Self::Error> {
+        match self.wifi.socket_read(self.spi, socket, buffer)? {
+            0 if !buffer.is_empty() => Err(nb::Error::Other(Error::SocketClosed)),
+            read => Ok(read),
+        }
+    }
+
+    fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Self::Error> {
+        self.wifi.socket_close(self.spi, &socket)
+    }
+}
+
+// A firmware socket used for UDP, along with the address it was connected to,
+// if any. The firmware addresses each datagram separately, so send looks that
+// address up rather than the socket remembering it.
+pub struct NalUdpSocket<'a, CS, S> {
+    socket: Socket<'a, CS, S>,
+    remote: Option<([u8; 4], u16)>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> UdpClientStack for NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+    SE: core::fmt::Debug,
+{
+    type UdpSocket = NalUdpSocket<'a, CS, S>;
+    type Error = Error<SE>;
+
+    fn socket(&mut self) -> Result<Self::UdpSocket, Self::Error> {
+        Ok(NalUdpSocket {
+            socket: self.wifi.socket_new(self.spi)?,
+            remote: None,
+        })
+    }
+
+    // The socket is also bound to a port the firmware picks, since otherwise
+    // it has nowhere to receive replies.
+    fn connect(
+        &mut self,
+        socket: &mut Self::UdpSocket,
+        remote: SocketAddr,
+    ) -> Result<(), Self::Error> {
+        let ip = ipv4_octets(&remote)?;
+
+        self.wifi
+            .server_start(self.spi, &socket.socket, Protocol::UDP, 0)?;
+
+        socket.remote = Some((ip, remote.port()));
+
+        Ok(())
+    }
+
+    fn send(&mut self, socket: &mut Self::UdpSocket, buffer: &[u8]) -> nb::Result<(), Self::Error> {
+        let (ip, port) = socket.remote.ok_or(Error::SocketNotConnected)?;
+
+        self.wifi
+            .udp_send_to(self.spi, &socket.socket, ip, port, &mut buffer.iter().cloned())?;
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        socket: &mut Self::UdpSocket,
+        buffer: &mut [u8],
+    ) -> nb::Result<(usize, SocketAddr), Self::Error> {
+        loop {
+            let (read, ip, port) = self.wifi.udp_recv_from(self.spi, &socket.socket, buffer)?;
+
+            // The firmware doesn’t filter by sender, so connected sockets drop
+            // datagrams from anyone else.
+            match socket.remote {
+                Some(remote) if remote != (ip, port) => continue,
+                _ => return Ok((read, socket_addr(ip, port))),
+            }
+        }
+    }
+
+    fn close(&mut self, socket: Self::UdpSocket) -> Result<(), Self::Error> {
+        self.wifi.socket_close(self.spi, &socket.socket)
+    }
+}
+
+impl<'a, CS, B, S, SE, T, TC> UdpFullStack for NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+    SE: core::fmt::Debug,
+{
+    fn bind(&mut self, socket: &mut Self::UdpSocket, local_port: u16) -> Result<(), Self::Error> {
+        self.wifi
+            .server_start(self.spi, &socket.socket, Protocol::UDP, local_port)
+    }
+
+    fn send_to(
+        &mut self,
+        socket: &mut Self::UdpSocket,
+        remote: SocketAddr,
+        buffer: &[u8],
+    ) -> nb::Result<(), Self::Error> {
+        let ip = ipv4_octets(&remote)?;
+
+        self.wifi.udp_send_to(
+            self.spi,
+            &socket.socket,
+            ip,
+            remote.port(),
+            &mut buffer.iter().cloned(),
+        )?;
+
+        Ok(())
+    }
+}
+
+impl<'a, CS, B, S, SE, T, TC> Dns for NinaStack<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+    SE: core::fmt::Debug,
+{
+    type Error = Error<SE>;
+
+    fn get_host_by_name(
+        &mut self,
+        hostname: &str,
+        addr_type: AddrType,
+    ) -> nb::Result<IpAddr, Self::Error> {
+        if addr_type == AddrType::IPv6 {
+            return Err(nb::Error::Other(Error::AddressNotSupported));
+        }
+
+        let ip = self.wifi.resolve_host_name(self.spi, hostname)?;
+
+        Ok(IpAddr::V4(Ipv4Addr::from(ip)))
+    }
+
+    // The firmware has no reverse lookup.
+    fn get_host_by_address(
+        &mut self,
+        _addr: IpAddr,
+        _result: &mut [u8],
+    ) -> nb::Result<usize, Self::Error> {
+        Err(nb::Error::Other(Error::AddressNotSupported))
+    }
+}
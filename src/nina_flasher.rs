@@ -0,0 +1,652 @@
+// Writes firmware to the ESP32’s flash through its ROM serial bootloader, so
+// that the NINA firmware can be updated from the host without a USB
+// passthrough sketch.
+//
+// The ESP32 needs to be put into its bootloader first, with
+// WifiNina::reset_to_bootloader, and the host needs a serial connection to
+// the ESP32’s UART.
+//
+// Packets are SLIP-framed. Requests are a direction byte (0), the command, a
+// 16-bit data length, a 32-bit checksum, and then the data. Responses are a
+// direction byte (1), the command, a 16-bit data length, a 32-bit value, and
+// then the data, the last 4 bytes of which are status.
+
+use embedded_hal::serial;
+use embedded_hal::timer::CountDown;
+
+use nb::block;
+
+use crate::util::md5::Md5;
+use crate::util::millis::{Milliseconds, U32Ext};
+use crate::util::timeout_iter::IntoTimeoutIter;
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+// The ROM writes flash in blocks of this size.
+const FLASH_BLOCK_SIZE: usize = 0x400;
+
+// The largest flash that NINA and AirLift modules ship with. The ROM only
+// uses this to bounds-check writes.
+const FLASH_SIZE: u32 = 4 * 1024 * 1024;
+
+const CHECKSUM_SEED: u8 = 0xEF;
+const STATUS_LEN: usize = 4;
+
+// Big enough for the MD5 response, which is the largest one we read.
+const MAX_RESPONSE_LEN: usize = 64;
+
+const DEFAULT_TIMEOUT: Milliseconds = Milliseconds(3_000);
+const SYNC_TIMEOUT: Milliseconds = Milliseconds(100);
+
+// Where the NINA firmware keeps the root CA bundle that TLS sockets check
+// servers against, from the flash layout in its combine.py.
+pub const ROOT_CERTIFICATES_OFFSET: u32 = 0x10000;
+const ROOT_CERTIFICATES_SIZE: usize = 0x20000;
+
+// Timeouts for commands whose duration scales with the amount of flash they
+// touch, in ms per MB.
+const ERASE_TIMEOUT_PER_MB: u32 = 30_000;
+const MD5_TIMEOUT_PER_MB: u32 = 8_000;
+
+#[derive(Debug, Copy, Clone)]
+#[repr(u8)]
+enum RomCommand {
+    FlashBegin = 0x02,
+    FlashData = 0x03,
+    FlashEnd = 0x04,
+    Sync = 0x08,
+    SpiSetParams = 0x0B,
+    SpiAttach = 0x0D,
+    SpiFlashMd5 = 0x13,
+}
+
+impl From<RomCommand> for u8 {
+    fn from(c: RomCommand) -> u8 {
+        c as u8
+    }
+}
+
+#[derive(Debug)]
+pub enum FlasherError<SerialError> {
+    Serial(SerialError),
+    Timeout,
+    // Bootloader didn’t respond to the sync command.
+    SyncFailed,
+    InvalidResponse,
+    // The bootloader responded to the command with a non-zero status. The
+    // second value is its error code.
+    CommandFailed(u8, u8),
+    ImageTooLarge,
+    VerifyFailed,
+    // The root CA bundle isn’t PEM, or isn’t NUL-terminated.
+    InvalidCertificates,
+}
+
+pub struct NinaFlasher<Serial, Timer> {
+    serial: Serial,
+    timer: Timer,
+}
+
+impl<Serial, SerialError, Timer, TimerTime> NinaFlasher<Serial, Timer>
+where
+    Serial: serial::Read<u8, Error = SerialError> + serial::Write<u8, Error = SerialError>,
+    Timer: CountDown<Time = TimerTime>,
+    TimerTime: From<Milliseconds>,
+{
+    pub fn new(serial: Serial, timer: Timer) -> Self {
+        NinaFlasher { serial, timer }
+    }
+
+    pub fn free(self) -> (Serial, Timer) {
+        (self.serial, self.timer)
+    }
+
+    // Establishes communication with the bootloader, which also lets it detect
+    // our baud rate.
+    pub fn sync(&mut self) -> Result<(), FlasherError<SerialError>> {
+        let mut data = [0x55u8; 36];
+        data[..4].copy_from_slice(&[0x07, 0x07, 0x12, 0x20]);
+
+        for _ in 0..10 {
+            match self.command(RomCommand::Sync, &data, 0, SYNC_TIMEOUT, &mut []) {
+                Ok(_) => {
+                    // The bootloader sends several responses to each sync, so
+                    // drain them to keep them from being read as responses to
+                    // later commands.
+                    while self.read_frame(SYNC_TIMEOUT, &mut [0u8; MAX_RESPONSE_LEN]).is_ok() {}
+
+                    return Ok(());
+                }
+                Err(FlasherError::Timeout) | Err(FlasherError::InvalidResponse) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(FlasherError::SyncFailed)
+    }
+
+    // Writes image to flash starting at offset, then checks it by comparing
+    // the bootloader’s MD5 of the written region against our own. The ESP32
+    // reboots into the new firmware afterwards.
+    pub fn flash_image(
+        &mut self,
+        offset: u32,
+        image: &[u8],
+    ) -> Result<(), FlasherError<SerialError>> {
+        let size = image.len() as u32;
+
+        if offset.checked_add(size).is_none_or(|end| end > FLASH_SIZE) {
+            return Err(FlasherError::ImageTooLarge);
+        }
+
+        self.attach_flash()?;
+
+        let num_blocks = image.len().div_ceil(FLASH_BLOCK_SIZE) as u32;
+
+        let mut begin = [0u8; 16];
+        begin[0..4].copy_from_slice(&size.to_le_bytes());
+        begin[4..8].copy_from_slice(&num_blocks.to_le_bytes());
+        begin[8..12].copy_from_slice(&(FLASH_BLOCK_SIZE as u32).to_le_bytes());
+        begin[12..16].copy_from_slice(&offset.to_le_bytes());
+
+        // Beginning erases the region that will be written.
+        self.command(
+            RomCommand::FlashBegin,
+            &begin,
+            0,
+            Self::timeout_for_size(ERASE_TIMEOUT_PER_MB, size),
+            &mut [],
+        )?;
+
+        for (seq, chunk) in image.chunks(FLASH_BLOCK_SIZE).enumerate() {
+            // Blocks are always sent at full size, padded with erased flash.
+            let mut block = [0xFFu8; 16 + FLASH_BLOCK_SIZE];
+            block[0..4].copy_from_slice(&(FLASH_BLOCK_SIZE as u32).to_le_bytes());
+            block[4..8].copy_from_slice(&(seq as u32).to_le_bytes());
+            block[8..16].copy_from_slice(&[0; 8]);
+            block[16..16 + chunk.len()].copy_from_slice(chunk);
+
+            let checksum = Self::checksum(&block[16..]);
+
+            self.command(RomCommand::FlashData, &block, checksum, DEFAULT_TIMEOUT, &mut [])?;
+        }
+
+        self.verify(offset, image)?;
+
+        // 0 tells the bootloader to reboot.
+        self.command(RomCommand::FlashEnd, &[0; 4], 0, DEFAULT_TIMEOUT, &mut [])?;
+
+        Ok(())
+    }
+
+    // Replaces the firmware’s root CA bundle, which is how servers with a
+    // private CA can be trusted by Protocol::TLS sockets. The firmware has no
+    // SPI command for adding to it.
+    //
+    // The bundle is concatenated PEM certificates, followed by a NUL (the
+    // firmware reads it as a C string). It replaces the built-in bundle
+    // entirely, so it needs to include any public roots that are still
+    // wanted.
+    pub fn flash_root_certificates(
+        &mut self,
+        bundle: &[u8],
+    ) -> Result<(), FlasherError<SerialError>> {
+        if !bundle.starts_with(b"-----BEGIN CERTIFICATE") || bundle.last() != Some(&0) {
+            return Err(FlasherError::InvalidCertificates);
+        }
+
+        if bundle.len() > ROOT_CERTIFICATES_SIZE {
+            return Err(FlasherError::ImageTooLarge);
+        }
+
+        self.flash_image(ROOT_CERTIFICATES_OFFSET, bundle)
+    }
+
+    fn attach_flash(&mut self) -> Result<(), FlasherError<SerialError>> {
+        // Attaches the default SPI flash pins.
+        self.command(RomCommand::SpiAttach, &[0; 8], 0, DEFAULT_TIMEOUT, &mut [])?;
+
+        let mut params = [0u8; 24];
+        // Words are: flash id, total size, block size, sector size, page size,
+        // and status mask.
+        params[4..8].copy_from_slice(&FLASH_SIZE.to_le_bytes());
+        params[8..12].copy_from_slice(&(64u32 * 1024).to_le_bytes());
+        params[12..16].copy_from_slice(&(4u32 * 1024).to_le_bytes());
+        params[16..20].copy_from_slice(&256u32.to_le_bytes());
+        params[20..24].copy_from_slice(&0xFFFFu32.to_le_bytes());
+
+        self.command(RomCommand::SpiSetParams, &params, 0, DEFAULT_TIMEOUT, &mut [])?;
+
+        Ok(())
+    }
+
+    fn verify(&mut self, offset: u32, image: &[u8]) -> Result<(), FlasherError<SerialError>> {
+        let size = image.len() as u32;
+
+        let mut md5_params = [0u8; 16];
+        md5_params[0..4].copy_from_slice(&offset.to_le_bytes());
+        md5_params[4..8].copy_from_slice(&size.to_le_bytes());
+
+        // The ROM reports the digest as 32 hex characters.
+        let mut flash_md5 = [0u8; 32];
+
+        let len = self.command(
+            RomCommand::SpiFlashMd5,
+            &md5_params,
+            0,
+            Self::timeout_for_size(MD5_TIMEOUT_PER_MB, size),
+            &mut flash_md5,
+        )?;
+
+        if len != flash_md5.len() {
+            return Err(FlasherError::InvalidResponse);
+        }
+
+        let mut md5 = Md5::new();
+        md5.update(image);
+
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+
+        let matches = md5.finalize().iter().enumerate().all(|(i, b)| {
+            flash_md5[i * 2].eq_ignore_ascii_case(&HEX[(b >> 4) as usize])
+                && flash_md5[i * 2 + 1].eq_ignore_ascii_case(&HEX[(b & 0xF) as usize])
+        });
+
+        if matches {
+            Ok(())
+        } else {
+            Err(FlasherError::VerifyFailed)
+        }
+    }
+
+    // Sends the command and waits for its response, copying the response’s
+    // data (minus status bytes) into data_out. Returns the length of that data.
+    fn command(
+        &mut self,
+        cmd: RomCommand,
+        data: &[u8],
+        checksum: u32,
+        timeout: Milliseconds,
+        data_out: &mut [u8],
+    ) -> Result<usize, FlasherError<SerialError>> {
+        let cmd_byte: u8 = cmd.into();
+
+        let mut header = [0u8; 8];
+        header[1] = cmd_byte;
+        header[2..4].copy_from_slice(&(data.len() as u16).to_le_bytes());
+        header[4..8].copy_from_slice(&checksum.to_le_bytes());
+
+        self.write_frame(&[&header, data])?;
+
+        let mut response = [0u8; MAX_RESPONSE_LEN];
+
+        // The bootloader may still be sending responses to earlier commands
+        // (such as extra syncs), so skip anything that isn’t ours.
+        for _ in 0..100 {
+            let len = self.read_frame(timeout, &mut response)?;
+
+            if len < 8 + STATUS_LEN || response[0] != 1 || response[1] != cmd_byte {
+                continue;
+            }
+
+            let data_len = u16::from_le_bytes([response[2], response[3]]) as usize;
+
+            if data_len < STATUS_LEN || 8 + data_len > len {
+                return Err(FlasherError::InvalidResponse);
+            }
+
+            let status = &response[8 + data_len - STATUS_LEN..8 + data_len];
+
+            if status[0] != 0 {
+                return Err(FlasherError::CommandFailed(cmd_byte, status[1]));
+            }
+
+            let out_len = core::cmp::min(data_len - STATUS_LEN, data_out.len());
+            data_out[..out_len].copy_from_slice(&response[8..8 + out_len]);
+
+            return Ok(data_len - STATUS_LEN);
+        }
+
+        Err(FlasherError::InvalidResponse)
+    }
+
+    fn write_frame(&mut self, parts: &[&[u8]]) -> Result<(), FlasherError<SerialError>> {
+        self.write_byte(SLIP_END)?;
+
+        for b in parts.iter().flat_map(|p| p.iter()) {
+            match *b {
+                SLIP_END => {
+                    self.write_byte(SLIP_ESC)?;
+                    self.write_byte(SLIP_ESC_END)?;
+                }
+                SLIP_ESC => {
+                    self.write_byte(SLIP_ESC)?;
+                    self.write_byte(SLIP_ESC_ESC)?;
+                }
+                b => self.write_byte(b)?,
+            }
+        }
+
+        self.write_byte(SLIP_END)?;
+
+        block!(self.serial.flush()).map_err(FlasherError::Serial)
+    }
+
+    fn write_byte(&mut self, b: u8) -> Result<(), FlasherError<SerialError>> {
+        block!(self.serial.write(b)).map_err(FlasherError::Serial)
+    }
+
+    // Reads a SLIP frame into buf, returning its unescaped length. Frames too
+    // large for buf are an InvalidResponse.
+    fn read_frame(
+        &mut self,
+        timeout: Milliseconds,
+        buf: &mut [u8],
+    ) -> Result<usize, FlasherError<SerialError>> {
+        while self.read_byte(timeout)? != SLIP_END {}
+
+        let mut len = 0;
+        let mut escaped = false;
+
+        loop {
+            let b = match (self.read_byte(timeout)?, escaped) {
+                // Back-to-back ENDs are an empty frame, which we skip over.
+                (SLIP_END, false) if len == 0 => continue,
+                (SLIP_END, false) => return Ok(len),
+                (SLIP_ESC, false) => {
+                    escaped = true;
+                    continue;
+                }
+                (SLIP_ESC_END, true) => SLIP_END,
+                (SLIP_ESC_ESC, true) => SLIP_ESC,
+                (_, true) => return Err(FlasherError::InvalidResponse),
+                (b, false) => b,
+            };
+
+            escaped = false;
+
+            if len == buf.len() {
+                return Err(FlasherError::InvalidResponse);
+            }
+
+            buf[len] = b;
+            len += 1;
+        }
+    }
+
+    fn read_byte(&mut self, timeout: Milliseconds) -> Result<u8, FlasherError<SerialError>> {
+        for _ in self.timer.timeout_iter(timeout) {
+            match self.serial.read() {
+                Ok(b) => return Ok(b),
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(err)) => return Err(FlasherError::Serial(err)),
+            }
+        }
+
+        Err(FlasherError::Timeout)
+    }
+
+    fn checksum(data: &[u8]) -> u32 {
+        data.iter().fold(CHECKSUM_SEED, |acc, b| acc ^ b) as u32
+    }
+
+    fn timeout_for_size(ms_per_mb: u32, size: u32) -> Milliseconds {
+        let scaled = (ms_per_mb as u64 * size as u64 / (1024 * 1024)) as u32;
+
+        core::cmp::max(DEFAULT_TIMEOUT.0, scaled).ms()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::util::mocks::MockTimer;
+
+    // A serial port that replays a script of bytes from the ESP32 and records
+    // everything written to it.
+    struct MockSerial {
+        rx: VecDeque<u8>,
+        tx: Vec<u8>,
+    }
+
+    impl serial::Read<u8> for MockSerial {
+        type Error = ();
+
+        fn read(&mut self) -> nb::Result<u8, ()> {
+            self.rx.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    impl serial::Write<u8> for MockSerial {
+        type Error = ();
+
+        fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+            self.tx.push(word);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    fn slip(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.push(SLIP_END);
+
+        for b in bytes {
+            match *b {
+                SLIP_END => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+                SLIP_ESC => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+                b => out.push(b),
+            }
+        }
+
+        out.push(SLIP_END);
+    }
+
+    fn respond(script: &mut Vec<u8>, cmd: RomCommand, data: &[u8], status: [u8; 4]) {
+        let mut packet = std::vec![1, cmd as u8];
+        packet.extend_from_slice(&((data.len() + 4) as u16).to_le_bytes());
+        packet.extend_from_slice(&[0; 4]);
+        packet.extend_from_slice(data);
+        packet.extend_from_slice(&status);
+
+        slip(script, &packet);
+    }
+
+    // Splits what was written into unescaped frames.
+    fn frames(tx: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let mut frame = Vec::new();
+        let mut escaped = false;
+
+        for b in tx {
+            match (*b, escaped) {
+                (SLIP_END, _) => {
+                    if !frame.is_empty() {
+                        frames.push(core::mem::take(&mut frame));
+                    }
+                }
+                (SLIP_ESC, false) => escaped = true,
+                (SLIP_ESC_END, true) => {
+                    frame.push(SLIP_END);
+                    escaped = false;
+                }
+                (SLIP_ESC_ESC, true) => {
+                    frame.push(SLIP_ESC);
+                    escaped = false;
+                }
+                (b, _) => frame.push(b),
+            }
+        }
+
+        frames
+    }
+
+    fn md5_hex(data: &[u8]) -> Vec<u8> {
+        let mut md5 = Md5::new();
+        md5.update(data);
+
+        md5.finalize()
+            .iter()
+            .flat_map(|b| std::format!("{:02x}", b).into_bytes())
+            .collect()
+    }
+
+    fn flasher(script: Vec<u8>) -> NinaFlasher<MockSerial, MockTimer> {
+        NinaFlasher::new(
+            MockSerial {
+                rx: script.into(),
+                tx: Vec::new(),
+            },
+            MockTimer::new(),
+        )
+    }
+
+    #[test]
+    fn sync_skips_extra_responses() {
+        let mut script = Vec::new();
+
+        for _ in 0..8 {
+            respond(&mut script, RomCommand::Sync, &[], [0; 4]);
+        }
+
+        let mut flasher = flasher(script);
+        flasher.sync().unwrap();
+
+        let (serial, _) = flasher.free();
+        let sent = frames(&serial.tx);
+
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0][1], RomCommand::Sync as u8);
+        assert_eq!(&sent[0][8..12], &[0x07, 0x07, 0x12, 0x20]);
+    }
+
+    #[test]
+    fn flashes_and_verifies_image() {
+        // Longer than a block, and full of bytes that need escaping.
+        let image: Vec<u8> = (0..FLASH_BLOCK_SIZE + 10)
+            .map(|i| [SLIP_END, SLIP_ESC, 0x42][i % 3])
+            .collect();
+
+        let mut script = Vec::new();
+        respond(&mut script, RomCommand::SpiAttach, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiSetParams, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashBegin, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashData, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashData, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiFlashMd5, &md5_hex(&image), [0; 4]);
+        respond(&mut script, RomCommand::FlashEnd, &[], [0; 4]);
+
+        let mut flasher = flasher(script);
+        flasher.flash_image(0x1000, &image).unwrap();
+
+        let (serial, _) = flasher.free();
+        let sent = frames(&serial.tx);
+
+        let commands: Vec<u8> = sent.iter().map(|f| f[1]).collect();
+        assert_eq!(commands, [0x0D, 0x0B, 0x02, 0x03, 0x03, 0x13, 0x04]);
+
+        // FlashBegin: size, number of blocks, block size, offset
+        assert_eq!(&sent[2][8..12], &((image.len() as u32).to_le_bytes()));
+        assert_eq!(&sent[2][12..16], &2u32.to_le_bytes());
+        assert_eq!(&sent[2][20..24], &0x1000u32.to_le_bytes());
+
+        // Second FlashData block is padded out with 0xFF, and the checksum
+        // covers the padded data.
+        let block = &sent[4];
+        assert_eq!(&block[8 + 4..8 + 8], &1u32.to_le_bytes());
+        assert_eq!(block.len(), 8 + 16 + FLASH_BLOCK_SIZE);
+        assert_eq!(&block[8 + 16..8 + 26], &image[FLASH_BLOCK_SIZE..]);
+        assert!(block[8 + 26..].iter().all(|b| *b == 0xFF));
+
+        let checksum = block[8 + 16..].iter().fold(0xEFu8, |acc, b| acc ^ b) as u32;
+        assert_eq!(&block[4..8], &checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn flashes_root_certificates_to_certs_partition() {
+        let bundle = b"-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n\0";
+
+        let mut script = Vec::new();
+        respond(&mut script, RomCommand::SpiAttach, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiSetParams, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashBegin, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashData, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiFlashMd5, &md5_hex(bundle), [0; 4]);
+        respond(&mut script, RomCommand::FlashEnd, &[], [0; 4]);
+
+        let mut flasher = flasher(script);
+        flasher.flash_root_certificates(bundle).unwrap();
+
+        let (serial, _) = flasher.free();
+        let sent = frames(&serial.tx);
+
+        assert_eq!(&sent[2][20..24], &0x10000u32.to_le_bytes());
+    }
+
+    #[test]
+    fn rejects_unterminated_root_certificates() {
+        let mut flasher = flasher(Vec::new());
+
+        match flasher.flash_root_certificates(b"-----BEGIN CERTIFICATE-----\n") {
+            Err(FlasherError::InvalidCertificates) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let (serial, _) = flasher.free();
+        assert!(serial.tx.is_empty());
+    }
+
+    #[test]
+    fn reports_mismatched_md5() {
+        let image = [1u8, 2, 3, 4];
+
+        let mut script = Vec::new();
+        respond(&mut script, RomCommand::SpiAttach, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiSetParams, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashBegin, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashData, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiFlashMd5, &md5_hex(&[0u8; 4]), [0; 4]);
+
+        let mut flasher = flasher(script);
+
+        match flasher.flash_image(0, &image) {
+            Err(FlasherError::VerifyFailed) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_command_failure_status() {
+        let mut script = Vec::new();
+        respond(&mut script, RomCommand::SpiAttach, &[], [0; 4]);
+        respond(&mut script, RomCommand::SpiSetParams, &[], [0; 4]);
+        respond(&mut script, RomCommand::FlashBegin, &[], [1, 0x05, 0, 0]);
+
+        let mut flasher = flasher(script);
+
+        match flasher.flash_image(0, &[0u8; 16]) {
+            Err(FlasherError::CommandFailed(0x02, 0x05)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn times_out_without_response() {
+        let mut flasher = flasher(Vec::new());
+
+        match flasher.flash_image(0, &[0u8; 16]) {
+            Err(FlasherError::Timeout) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+// Handles for the ESP32’s spare GPIOs, so that whatever is wired to them can
+// be driven through embedded-hal traits. On the Nano 33 IoT and the AirLift
+// boards, this includes the RGB status LED on pins 25–27.
+//
+// The handles share the WifiNina and SPI bus through a RefCell, so that
+// several can be held at once (such as one for each color of the LED). Each
+// pin can only have one handle at a time.
+
+use core::cell::{Cell, RefCell};
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+use embedded_hal::timer::CountDown;
+use embedded_hal::PwmPin;
+
+use crate::commands::pins::PinMode;
+use crate::util::millis::Milliseconds;
+use crate::{Error, WifiNina};
+
+// GPIOs that the firmware doesn’t use itself. 32–39 have ADC support.
+const SPARE_PINS: [u8; 9] = [25, 26, 27, 32, 33, 34, 35, 36, 39];
+
+// The spare pins that can be driven. 34 and up are input-only.
+const OUTPUT_PINS: [u8; 5] = [25, 26, 27, 32, 33];
+
+impl<CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime>
+    WifiNina<CsPin, BusyPin, Spi, CountDown>
+where
+    BusyPin: InputPin,
+    CsPin: OutputPin,
+    Spi: FullDuplex<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::Write<u8, Error = SpiError>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SpiError>,
+    CountDown: embedded_hal::timer::CountDown<Time = CountDownTime>,
+    CountDownTime: From<Milliseconds>,
+{
+    pub fn pins<'a>(
+        &'a mut self,
+        spi: &'a mut Spi,
+    ) -> NinaPins<'a, CsPin, BusyPin, Spi, SpiError, CountDown, CountDownTime> {
+        NinaPins::new(spi, self)
+    }
+}
+
+pub struct NinaPins<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    inner: RefCell<(&'a mut S, &'a mut WifiNina<CS, B, S, T>)>,
+    // Bit for each of SPARE_PINS that currently has a handle.
+    taken: Cell<u16>,
+}
+
+impl<'a, CS, B, S, SE, T, TC> NinaPins<'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn new(spi: &'a mut S, wifi: &'a mut WifiNina<CS, B, S, T>) -> Self {
+        NinaPins {
+            inner: RefCell::new((spi, wifi)),
+            taken: Cell::new(0),
+        }
+    }
+
+    pub fn output(
+        &self,
+        pin: u8,
+    ) -> Result<NinaOutputPin<'_, 'a, CS, B, S, SE, T, TC>, Error<SE>> {
+        if !OUTPUT_PINS.contains(&pin) {
+            return Err(Error::InvalidPin(pin));
+        }
+
+        self.claim(pin, PinMode::Output)?;
+
+        Ok(NinaOutputPin {
+            pins: self,
+            pin,
+            duty: 0,
+            enabled: false,
+        })
+    }
+
+    pub fn input(
+        &self,
+        pin: u8,
+        pull_up: bool,
+    ) -> Result<NinaInputPin<'_, 'a, CS, B, S, SE, T, TC>, Error<SE>> {
+        self.claim(
+            pin,
+            if pull_up {
+                PinMode::InputPullUp
+            } else {
+                PinMode::Input
+            },
+        )?;
+
+        Ok(NinaInputPin { pins: self, pin })
+    }
+
+    // Marks the pin as having a handle and sets its mode.
+    fn claim(&self, pin: u8, mode: PinMode) -> Result<(), Error<SE>> {
+        let bit = Self::pin_bit(pin).ok_or(Error::InvalidPin(pin))?;
+
+        if self.taken.get() & bit != 0 {
+            return Err(Error::PinInUse(pin));
+        }
+
+        self.with_wifi(|wifi, spi| wifi.pin_mode(spi, pin, mode))?;
+        self.taken.set(self.taken.get() | bit);
+
+        Ok(())
+    }
+
+    fn release(&self, pin: u8) {
+        if let Some(bit) = Self::pin_bit(pin) {
+            self.taken.set(self.taken.get() & !bit);
+        }
+    }
+
+    fn pin_bit(pin: u8) -> Option<u16> {
+        SPARE_PINS
+            .iter()
+            .position(|&p| p == pin)
+            .map(|i| 1 << i)
+    }
+
+    fn with_wifi<R>(
+        &self,
+        f: impl FnOnce(&mut WifiNina<CS, B, S, T>, &mut S) -> Result<R, Error<SE>>,
+    ) -> Result<R, Error<SE>> {
+        let mut inner = self.inner.borrow_mut();
+        let (ref mut spi, ref mut wifi) = *inner;
+
+        f(wifi, spi)
+    }
+}
+
+pub struct NinaOutputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pins: &'p NinaPins<'a, CS, B, S, SE, T, TC>,
+    pin: u8,
+
+    duty: u8,
+    enabled: bool,
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> NinaOutputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    fn write_duty(&self, duty: u8) -> Result<(), Error<SE>> {
+        let pin = self.pin;
+
+        self.pins
+            .with_wifi(|wifi, spi| wifi.pin_analog_write(spi, pin, duty))
+    }
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> OutputPin for NinaOutputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    type Error = Error<SE>;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let pin = self.pin;
+
+        self.pins
+            .with_wifi(|wifi, spi| wifi.pin_digital_write(spi, pin, false))
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let pin = self.pin;
+
+        self.pins
+            .with_wifi(|wifi, spi| wifi.pin_digital_write(spi, pin, true))
+    }
+}
+
+// PwmPin has no way to report errors, so any from the chip are dropped.
+impl<'p, 'a, CS, B, S, SE, T, TC> PwmPin for NinaOutputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    type Duty = u8;
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.write_duty(0).ok();
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        self.write_duty(self.duty).ok();
+    }
+
+    fn get_duty(&self) -> u8 {
+        self.duty
+    }
+
+    fn get_max_duty(&self) -> u8 {
+        u8::MAX
+    }
+
+    fn set_duty(&mut self, duty: u8) {
+        self.duty = duty;
+
+        if self.enabled {
+            self.write_duty(duty).ok();
+        }
+    }
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> Drop for NinaOutputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    fn drop(&mut self) {
+        self.pins.release(self.pin);
+    }
+}
+
+pub struct NinaInputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pins: &'p NinaPins<'a, CS, B, S, SE, T, TC>,
+    pin: u8,
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> NinaInputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    pub fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    // Raw 12-bit ADC reading. Only pins 32 and up support this.
+    pub fn analog_read(&self) -> Result<u16, Error<SE>> {
+        let pin = self.pin;
+
+        self.pins
+            .with_wifi(|wifi, spi| wifi.pin_analog_read(spi, pin))
+    }
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> InputPin for NinaInputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    type Error = Error<SE>;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        let pin = self.pin;
+
+        self.pins
+            .with_wifi(|wifi, spi| wifi.pin_digital_read(spi, pin))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+impl<'p, 'a, CS, B, S, SE, T, TC> Drop for NinaInputPin<'p, 'a, CS, B, S, SE, T, TC>
+where
+    CS: OutputPin,
+    B: InputPin,
+    S: FullDuplex<u8, Error = SE>
+        + embedded_hal::blocking::spi::Write<u8, Error = SE>
+        + embedded_hal::blocking::spi::WriteIter<u8, Error = SE>,
+    T: CountDown<Time = TC>,
+    TC: From<Milliseconds>,
+{
+    fn drop(&mut self) {
+        self.pins.release(self.pin);
+    }
+}
@@ -0,0 +1,130 @@
+// Stand-ins for the hardware, shared by the tests.
+
+extern crate std;
+
+use core::cell::Cell;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+use embedded_hal::timer::CountDown;
+
+use crate::util::millis::Milliseconds;
+use crate::WifiNina;
+
+// Records what’s written and replays a script of response bytes.
+pub struct MockSpi {
+    pub rx: VecDeque<u8>,
+    pub tx: Vec<u8>,
+}
+
+impl MockSpi {
+    pub fn new() -> Self {
+        MockSpi {
+            rx: VecDeque::new(),
+            tx: Vec::new(),
+        }
+    }
+}
+
+impl FullDuplex<u8> for MockSpi {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, ()> {
+        Ok(self.rx.pop_front().unwrap_or(0))
+    }
+
+    fn send(&mut self, _: u8) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl embedded_hal::blocking::spi::Write<u8> for MockSpi {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+        self.tx.extend_from_slice(words);
+        Ok(())
+    }
+}
+
+impl embedded_hal::blocking::spi::WriteIter<u8> for MockSpi {
+    type Error = ();
+
+    fn write_iter<WI: IntoIterator<Item = u8>>(&mut self, words: WI) -> Result<(), ()> {
+        self.tx.extend(words);
+        Ok(())
+    }
+}
+
+pub struct MockPin;
+
+impl OutputPin for MockPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+// Flips on every read, so that waits for either level succeed.
+pub struct MockBusy(Cell<bool>);
+
+impl InputPin for MockBusy {
+    type Error = ();
+
+    fn is_high(&self) -> Result<bool, ()> {
+        self.0.set(!self.0.get());
+        Ok(self.0.get())
+    }
+
+    fn is_low(&self) -> Result<bool, ()> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+// Expires after a fixed number of polls, so that reads from an exhausted
+// script time out instead of spinning forever.
+pub struct MockTimer {
+    remaining: u32,
+}
+
+impl MockTimer {
+    pub fn new() -> Self {
+        MockTimer { remaining: 0 }
+    }
+}
+
+impl CountDown for MockTimer {
+    type Time = Milliseconds;
+
+    fn start<T: Into<Milliseconds>>(&mut self, _: T) {
+        self.remaining = 10;
+    }
+
+    fn wait(&mut self) -> nb::Result<(), void::Void> {
+        if self.remaining == 0 {
+            Ok(())
+        } else {
+            self.remaining -= 1;
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+pub fn wifi(spi: &mut MockSpi) -> WifiNina<MockPin, MockBusy, MockSpi, MockTimer> {
+    WifiNina::new(
+        spi,
+        MockPin,
+        MockBusy(Cell::new(false)),
+        &mut MockPin,
+        MockTimer::new(),
+        None,
+    )
+    .unwrap()
+}
@@ -1,3 +1,6 @@
+pub mod md5;
+#[cfg(test)]
+pub mod mocks;
 pub mod millis;
 pub mod safe_spi;
 pub mod spi_ext;
@@ -33,12 +33,12 @@ impl<'a, S, CS: ChipSelect<Spi = S>> core::ops::Deref for SafeSpi<'a, S, CS> {
     type Target = S;
 
     fn deref(&self) -> &Self::Target {
-        &self.spi
+        self.spi
     }
 }
 
 impl<'a, S, CS: ChipSelect<Spi = S>> core::ops::DerefMut for SafeSpi<'a, S, CS> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.spi
+        self.spi
     }
 }
@@ -23,7 +23,7 @@ pub trait IntoTimeoutIter: CountDown
 where
     Self: core::marker::Sized,
 {
-    fn timeout_iter<CT>(&mut self, timeout: CT) -> TimeoutIter<Self>
+    fn timeout_iter<CT>(&mut self, timeout: CT) -> TimeoutIter<'_, Self>
     where
         CT: Into<Self::Time>,
     {